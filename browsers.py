@@ -0,0 +1,44 @@
+import csv
+import io
+import urllib.parse
+
+from sources import LOGIN, Entry, Source, Uri
+
+CHROME = 'chrome'
+FIREFOX = 'firefox'
+
+# Edge exports the same columns as Chrome
+COLUMNS = {
+    CHROME: {'name', 'url', 'username', 'password'},
+    FIREFOX: {'url', 'username', 'password', 'httpRealm', 'formActionOrigin'},
+}
+
+
+def detect(text):
+    header = set(next(csv.reader(io.StringIO(text)), []))
+    for browser in (FIREFOX, CHROME):
+        if COLUMNS[browser] <= header:
+            return browser
+    return None
+
+
+class BrowserSource(Source):
+    def __init__(self, text):
+        self._entries = []
+        for row in csv.DictReader(io.StringIO(text, newline='')):
+            url = row.get('url') or ''
+            name = row.get('name') or urllib.parse.urlparse(url).hostname
+            self._entries.append(Entry(
+                name=name or url,
+                type=LOGIN,
+                username=row.get('username'),
+                password=row.get('password'),
+                uris=[Uri(url)] if url else [],
+                notes=row.get('note'),
+            ))
+
+    def folders(self):
+        return []
+
+    def entries(self):
+        return self._entries
@@ -0,0 +1,166 @@
+import base64
+import hashlib
+import hmac
+import json
+
+from cryptography.hazmat.primitives import hashes, padding, serialization
+from cryptography.hazmat.primitives.asymmetric import padding as asym_padding
+from cryptography.hazmat.primitives.ciphers import Cipher, algorithms, modes
+from cryptography.hazmat.primitives.kdf.argon2 import Argon2id
+from cryptography.hazmat.primitives.kdf.hkdf import HKDFExpand
+
+KDF_PBKDF2 = 0
+KDF_ARGON2ID = 1
+
+AES_CBC_256_HMAC_SHA256 = 2
+RSA_2048_OAEP_SHA1 = 4
+
+
+class DecryptionError(Exception):
+    pass
+
+
+class SymmetricKey:
+    def __init__(self, enc, mac):
+        self.enc = enc
+        self.mac = mac
+
+    @classmethod
+    def from_bytes(cls, key):
+        if len(key) != 64:
+            raise DecryptionError(f"Unexpected key length {len(key)}")
+        return cls(key[:32], key[32:])
+
+
+def derive_key(password, salt, kdf, iterations, memory=None, parallelism=None):
+    password = password.encode('utf-8')
+    salt = salt.encode('utf-8')
+    if kdf == KDF_PBKDF2:
+        return hashlib.pbkdf2_hmac('sha256', password, salt, iterations, 32)
+    if kdf == KDF_ARGON2ID:
+        # Bitwarden feeds Argon2 the SHA-256 of the salt, memory is in MiB
+        argon = Argon2id(salt=hashlib.sha256(salt).digest(), length=32,
+                         iterations=iterations, lanes=parallelism,
+                         memory_cost=memory * 1024)
+        return argon.derive(password)
+    raise DecryptionError(f"Unsupported KDF type {kdf}")
+
+
+def stretch_key(key):
+    enc = HKDFExpand(hashes.SHA256(), 32, b'enc').derive(key)
+    mac = HKDFExpand(hashes.SHA256(), 32, b'mac').derive(key)
+    return SymmetricKey(enc, mac)
+
+
+def is_cipher_string(value):
+    if not isinstance(value, str):
+        return False
+    return (value.startswith(f"{AES_CBC_256_HMAC_SHA256}.") and
+            value.count('|') == 2)
+
+
+def decrypt_bytes(cipher_string, key):
+    enc_type, _, payload = cipher_string.partition('.')
+    if enc_type != str(AES_CBC_256_HMAC_SHA256):
+        raise DecryptionError(f"Unsupported encryption type {enc_type}")
+
+    try:
+        iv, data, mac = (base64.b64decode(p) for p in payload.split('|'))
+    except ValueError:
+        raise DecryptionError("Malformed cipher string")
+
+    expected = hmac.new(key.mac, iv + data, hashlib.sha256).digest()
+    if not hmac.compare_digest(mac, expected):
+        raise DecryptionError("MAC mismatch, wrong password or corrupted data")
+
+    decryptor = Cipher(algorithms.AES(key.enc), modes.CBC(iv)).decryptor()
+    padded = decryptor.update(data) + decryptor.finalize()
+    unpadder = padding.PKCS7(128).unpadder()
+    return unpadder.update(padded) + unpadder.finalize()
+
+
+def decrypt_string(cipher_string, key):
+    return decrypt_bytes(cipher_string, key).decode('utf-8')
+
+
+def load_private_key(cipher_string, key):
+    data = decrypt_bytes(cipher_string, key)
+    return serialization.load_der_private_key(data, password=None)
+
+
+def decrypt_rsa(cipher_string, private_key):
+    enc_type, _, payload = cipher_string.partition('.')
+    if enc_type != str(RSA_2048_OAEP_SHA1):
+        raise DecryptionError(f"Unsupported encryption type {enc_type}")
+
+    oaep = asym_padding.OAEP(mgf=asym_padding.MGF1(hashes.SHA1()),
+                             algorithm=hashes.SHA1(), label=None)
+    try:
+        return private_key.decrypt(base64.b64decode(payload), oaep)
+    except ValueError:
+        raise DecryptionError("RSA decryption failed")
+
+
+def master_password_hash(master_key, password):
+    return base64.b64encode(hashlib.pbkdf2_hmac(
+        'sha256', master_key, password.encode('utf-8'), 1, 32)).decode()
+
+
+def make_master_key(email, password, kdf, iterations, memory=None,
+                    parallelism=None):
+    return derive_key(password, email.strip().lower(), kdf, iterations, memory,
+                      parallelism)
+
+
+def unwrap_user_key(protected_key, master_key):
+    return SymmetricKey.from_bytes(decrypt_bytes(protected_key,
+                                                 stretch_key(master_key)))
+
+
+def make_user_key(email, password, protected_key, kdf, iterations, memory=None,
+                  parallelism=None):
+    master_key = make_master_key(email, password, kdf, iterations, memory,
+                                 parallelism)
+    return unwrap_user_key(protected_key, master_key)
+
+
+def decrypt_values(value, key):
+    if is_cipher_string(value):
+        return decrypt_string(value, key)
+    if isinstance(value, list):
+        return [decrypt_values(v, key) for v in value]
+    if isinstance(value, dict):
+        # Nested keys (attachments) are binary and decrypted when used
+        return {k: v if k == 'key' else decrypt_values(v, key)
+                for k, v in value.items()}
+    return value
+
+
+def decrypt_item(item, user_key):
+    key = user_key
+    # Newer clients wrap each cipher in its own key
+    if item.get('key'):
+        key = SymmetricKey.from_bytes(decrypt_bytes(item['key'], user_key))
+    item = {k: v for k, v in item.items() if k != 'key'}
+    return decrypt_values(item, key)
+
+
+def decrypt_account_restricted(js, user_key):
+    decrypt_bytes(js['encKeyValidation_DO_NOT_EDIT'], user_key)
+
+    plain = {k: decrypt_values(v, user_key) for k, v in js.items()
+             if k not in ('encKeyValidation_DO_NOT_EDIT', 'items')}
+    plain['encrypted'] = False
+    plain['items'] = [decrypt_item(i, user_key) for i in js.get('items', [])]
+    return plain
+
+
+def decrypt_password_protected(js, password):
+    key = derive_key(password, js['salt'], js['kdfType'], js['kdfIterations'],
+                     js.get('kdfMemory'), js.get('kdfParallelism'))
+    key = stretch_key(key)
+
+    # Only succeeds when the password is right, the content itself is unused
+    decrypt_bytes(js['encKeyValidation_DO_NOT_EDIT'], key)
+
+    return json.loads(decrypt_string(js['data'], key))
@@ -0,0 +1,63 @@
+import json
+import urllib.error
+import urllib.parse
+import urllib.request
+
+DEFAULT_URL = 'http://localhost:8087'
+LOCAL_HOSTS = ('localhost', '127.0.0.1', '::1')
+
+
+class ServeError(Exception):
+    pass
+
+
+def get(url, path):
+    try:
+        with urllib.request.urlopen(f"{url.rstrip('/')}{path}") as r:
+            js = json.load(r)
+    except (urllib.error.URLError, ValueError) as e:
+        raise ServeError(f"{path}: {e}")
+
+    if not isinstance(js, dict):
+        raise ServeError(f"{path}: unexpected response")
+    if not js.get('success', False):
+        raise ServeError(f"{path}: {js.get('message', 'request failed')}")
+    if not isinstance(js.get('data'), dict):
+        raise ServeError(f"{path}: unexpected response")
+    return js['data']
+
+
+def get_list(url, path):
+    objects = get(url, path).get('data')
+    if not isinstance(objects, list) or not all(isinstance(o, dict)
+                                                for o in objects):
+        raise ServeError(f"{path}: unexpected response")
+    return objects
+
+
+def load_serve(url, trash=False):
+    # bw serve hands out the decrypted vault, never talk to it remotely
+    if urllib.parse.urlparse(url).hostname not in LOCAL_HOSTS:
+        raise ServeError(f"{url} is not a local address")
+
+    template = get(url, '/status').get('template')
+    if not isinstance(template, dict) or 'status' not in template:
+        raise ServeError("/status: unexpected response")
+    status = template['status']
+    if status != 'unlocked':
+        raise ServeError(f"Vault is {status}, run 'bw unlock' first")
+
+    # The folder list includes a "No Folder" entry without an id
+    folders = [{'id': f['id'], 'name': f.get('name')}
+               for f in get_list(url, '/list/object/folders') if f.get('id')]
+    # Listed items are complete, /object/item/{id} would return the same
+    items = get_list(url, '/list/object/items')
+    # Trashed items are only listed on request
+    if trash:
+        items += get_list(url, '/list/object/items?trash')
+
+    return {
+        'encrypted': False,
+        'folders': folders,
+        'items': items,
+    }
@@ -0,0 +1,158 @@
+import base64
+import getpass
+import json
+import urllib.error
+import urllib.parse
+import urllib.request
+import uuid
+
+import bwcrypto
+
+CLIENT_ID = 'cli'
+DEVICE_TYPE = 25  # Linux CLI
+DEVICE_NAME = 'bw2mp'
+
+TWO_FACTOR_AUTHENTICATOR = 0
+TWO_FACTOR_EMAIL = 1
+
+
+class ServerError(Exception):
+    pass
+
+
+def camel_keys(value):
+    # Older Vaultwarden releases answer in PascalCase
+    if isinstance(value, list):
+        return [camel_keys(v) for v in value]
+    if isinstance(value, dict):
+        return {k[:1].lower() + k[1:]: camel_keys(v) for k, v in value.items()}
+    return value
+
+
+def request(url, data=None, form=False, headers=None):
+    headers = dict(headers or {})
+    body = None
+    if data is not None:
+        if form:
+            body = urllib.parse.urlencode(data).encode()
+            headers['Content-Type'] = 'application/x-www-form-urlencoded'
+        else:
+            body = json.dumps(data).encode()
+            headers['Content-Type'] = 'application/json'
+
+    try:
+        with urllib.request.urlopen(urllib.request.Request(url, body,
+                                                           headers)) as r:
+            return camel_keys(json.load(r))
+    except urllib.error.HTTPError as e:
+        try:
+            return camel_keys(json.load(e))
+        except ValueError:
+            raise ServerError(f"{url}: {e}")
+    except (urllib.error.URLError, ValueError) as e:
+        raise ServerError(f"{url}: {e}")
+
+
+def prelogin(server, email):
+    js = request(f"{server}/identity/accounts/prelogin", {'email': email})
+    if 'kdf' not in js:
+        raise ServerError(f"Prelogin failed: {js}")
+    return js
+
+
+def login(server, email, password_hash):
+    form = {
+        'grant_type': 'password',
+        'username': email,
+        'password': password_hash,
+        'scope': 'api offline_access',
+        'client_id': CLIENT_ID,
+        'deviceType': DEVICE_TYPE,
+        'deviceIdentifier': str(uuid.uuid4()),
+        'deviceName': DEVICE_NAME,
+    }
+    auth_email = base64.urlsafe_b64encode(email.encode()).decode()
+    headers = {'Auth-Email': auth_email.rstrip('=')}
+
+    url = f"{server}/identity/connect/token"
+    js = request(url, form, form=True, headers=headers)
+
+    providers = js.get('twoFactorProviders')
+    if providers:
+        providers = [int(p) for p in providers]
+        provider = next((p for p in (TWO_FACTOR_AUTHENTICATOR,
+                                     TWO_FACTOR_EMAIL) if p in providers),
+                        None)
+        if provider is None:
+            raise ServerError("Only authenticator and email two-step login "
+                              "are supported")
+        form['twoFactorToken'] = getpass.getpass("Two-step login code: ")
+        form['twoFactorProvider'] = provider
+        js = request(url, form, form=True, headers=headers)
+
+    if 'access_token' not in js:
+        message = js.get('error_description') or js.get('message') or js
+        raise ServerError(f"Login failed: {message}")
+    return js['access_token']
+
+
+def organization_keys(profile, user_key):
+    if not profile.get('organizations'):
+        return {}
+
+    private_key = bwcrypto.load_private_key(profile['privateKey'], user_key)
+    keys = {}
+    # Unconfirmed memberships come without a usable key
+    for o in profile['organizations']:
+        if not o.get('key'):
+            continue
+        try:
+            keys[o['id']] = bwcrypto.SymmetricKey.from_bytes(
+                bwcrypto.decrypt_rsa(o['key'], private_key))
+        except bwcrypto.DecryptionError:
+            pass
+    return keys
+
+
+def load_server(server, email, password):
+    server = server.rstrip('/')
+
+    kdf = prelogin(server, email)
+    master_key = bwcrypto.make_master_key(email, password, kdf['kdf'],
+                                          kdf['kdfIterations'],
+                                          kdf.get('kdfMemory'),
+                                          kdf.get('kdfParallelism'))
+    token = login(server, email,
+                  bwcrypto.master_password_hash(master_key, password))
+
+    sync = request(f"{server}/api/sync?excludeDomains=true",
+                   headers={'Authorization': f"Bearer {token}"})
+    if 'profile' not in sync:
+        raise ServerError(f"Sync failed: {sync.get('message', sync)}")
+
+    user_key = bwcrypto.unwrap_user_key(sync['profile']['key'], master_key)
+    org_keys = organization_keys(sync['profile'], user_key)
+
+    items = []
+    skipped = []
+    for c in sync.get('ciphers', []):
+        # 'data' repeats the cipher fields in an older layout
+        c = {k: v for k, v in c.items() if k != 'data'}
+        key = user_key
+        if c.get('organizationId'):
+            key = org_keys.get(c['organizationId'])
+            if key is None:
+                skipped.append(c)
+                continue
+        items.append(bwcrypto.decrypt_item(c, key))
+
+    collections = [bwcrypto.decrypt_values(c, org_keys[c['organizationId']])
+                   for c in sync.get('collections', [])
+                   if c.get('organizationId') in org_keys]
+
+    return {
+        'encrypted': False,
+        'folders': bwcrypto.decrypt_values(sync.get('folders', []), user_key),
+        'collections': collections,
+        'items': items,
+    }, skipped
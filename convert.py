@@ -1,93 +1,762 @@
 #!/usr/bin/env python
 import argparse
+import csv
+import getpass
+import io
+import os
 import pprint
 import sys
 import json
+import zipfile
 
+import browsers
+import bwcrypto
+import bwserve
+import bwserver
+import fields
+import keepass
+import mooltipass
+import psl
+import selection
+import onepassword
+import sources
+import sshkeys
+
+
+KDF_TYPES = {
+    'pbkdf2': bwcrypto.KDF_PBKDF2,
+    'argon2id': bwcrypto.KDF_ARGON2ID,
+}
+
+# Bitwarden's defaults, Argon2id passes are far more expensive
+KDF_ITERATIONS = {
+    'pbkdf2': 600000,
+    'argon2id': 3,
+}
+
+
+def account_user_key(args):
+    if not args.email or not args.user_key:
+        print("Error: Account restricted exports need --email and --user-key")
+        exit()
+
+    password = getpass.getpass("Master password: ")
+    return bwcrypto.make_user_key(args.email, password, args.user_key,
+                                  KDF_TYPES[args.kdf], args.kdf_iterations,
+                                  args.kdf_memory, args.kdf_parallelism)
+
+
+def parse_json(text, file, args):
+    js = json.loads(text)
+
+    if js.get('encrypted', False):
+        try:
+            if js.get('passwordProtected', False):
+                password = getpass.getpass("Export password: ")
+                js = bwcrypto.decrypt_password_protected(js, password)
+            else:
+                user_key = account_user_key(args)
+                js = bwcrypto.decrypt_account_restricted(js, user_key)
+        except bwcrypto.DecryptionError as e:
+            print(f"Error: Couldn't decrypt {file}: {e}")
+            exit()
 
-def load_json(file):
-    js = None
-    with open(file, 'r') as j:
-        js = json.load(j)
     return js
 
 
+ZIP_DATA = 'data.json'
+ZIP_ATTACHMENTS = 'attachments'
+
+
+def load_zip(data, file, args):
+    with zipfile.ZipFile(io.BytesIO(data)) as z:
+        try:
+            js = parse_json(z.read(ZIP_DATA).decode('utf-8'), file, args)
+        except KeyError:
+            print(f"Error: No {ZIP_DATA} in {file}")
+            exit()
+
+        # Attachments are stored as attachments/<item id>/<file name>
+        blobs = {}
+        for info in z.infolist():
+            parts = info.filename.split('/')
+            if info.is_dir() or len(parts) != 3 or parts[0] != ZIP_ATTACHMENTS:
+                continue
+            blobs.setdefault(parts[1], {})[parts[2]] = z.read(info)
+
+    attach_files(js, blobs)
+    return js
+
+
+def load_attachments_dir(directory):
+    # Same <item id>/<file name> layout as in the ZIP export
+    blobs = {}
+    for item_id in os.listdir(directory):
+        item_dir = os.path.join(directory, item_id)
+        if not os.path.isdir(item_dir):
+            continue
+        for name in os.listdir(item_dir):
+            with open(os.path.join(item_dir, name), 'rb') as f:
+                blobs.setdefault(item_id, {})[name] = f.read()
+    return blobs
+
+
+def attach_files(js, blobs):
+    for i in js['items']:
+        files = blobs.get(i.get('id'))
+        if not files:
+            continue
+
+        attachments = {a.get('fileName'): a
+                       for a in i.get('attachments') or []}
+        for name, data in files.items():
+            attachment = attachments.setdefault(name, {'fileName': name})
+            attachment['size'] = len(data)
+            attachment['data'] = data
+        i['attachments'] = list(attachments.values())
+
+
+CSV_ITEM_TYPES = {
+    'login': 1,
+    'note': 2,
+}
+
+
+def csv_fields(text):
+    result = []
+    for line in text.splitlines():
+        name, _, value = line.partition(': ')
+        result.append({'name': name, 'value': value, 'type': 0})
+    return result
+
+
+def load_csv(text):
+    folders = {}
+    items = []
+    with io.StringIO(text, newline='') as c:
+        for n, row in enumerate(csv.DictReader(c)):
+            # The CSV only carries folder names, make up stable ids for them
+            folder = row.get('folder') or None
+            if folder and folder not in folders:
+                folders[folder] = f"csv-folder-{len(folders)}"
+
+            item = {
+                'id': f"csv-item-{n}",
+                'folderId': folders.get(folder),
+                'type': CSV_ITEM_TYPES.get(row.get('type'), 1),
+                'name': row.get('name', ''),
+                'notes': row.get('notes') or None,
+                'favorite': row.get('favorite') == '1',
+                'reprompt': int(row.get('reprompt') or 0),
+                'fields': csv_fields(row.get('fields') or ''),
+            }
+
+            if item['type'] == 1:
+                uris = (row.get('login_uri') or '').splitlines()
+                item['login'] = {
+                    'username': row.get('login_username') or None,
+                    'password': row.get('login_password') or None,
+                    'totp': row.get('login_totp') or None,
+                    'uris': [{'uri': u, 'match': None} for u in uris if u],
+                }
+
+            items.append(item)
+
+    return {
+        'encrypted': False,
+        'folders': [{'id': i, 'name': n} for n, i in folders.items()],
+        'items': items,
+    }
+
+
+def from_stdin(file):
+    return file is None or file == '-'
+
+
+FORMATS = ['json', 'csv', 'zip', 'kdbx', '1pux', browsers.CHROME,
+           browsers.FIREFOX]
+
+
+def guess_format(file, data):
+    if not from_stdin(file):
+        ext = os.path.splitext(file)[1].lower().lstrip('.')
+        if ext in FORMATS:
+            return ext
+
+    if data.startswith(keepass.SIGNATURE):
+        return 'kdbx'
+    if data.startswith(b'PK'):
+        with zipfile.ZipFile(io.BytesIO(data)) as z:
+            if onepassword.EXPORT_DATA in z.namelist():
+                return '1pux'
+        return 'zip'
+    if data.lstrip().startswith(b'{'):
+        return 'json'
+    return 'csv'
+
+
+def load_vault(file, args):
+    if from_stdin(file):
+        data = sys.stdin.buffer.read()
+        file = '<stdin>'
+    else:
+        with open(file, 'rb') as f:
+            data = f.read()
+
+    fmt = args.format or guess_format(file, data)
+    if fmt == 'kdbx':
+        return load_keepass(data, file, args)
+
+    if fmt == 'zip':
+        return bitwarden_source(load_zip(data, file, args), args)
+    if fmt == '1pux':
+        try:
+            return onepassword.OnePuxSource(io.BytesIO(data))
+        except onepassword.OnePasswordError as e:
+            print(f"Error: Couldn't read {file}: {e}")
+            exit()
+
+    text = data.decode('utf-8-sig')
+    if fmt == 'csv':
+        fmt = browsers.detect(text) or fmt
+
+    if fmt in (browsers.CHROME, browsers.FIREFOX):
+        return browsers.BrowserSource(text)
+    if fmt == 'csv':
+        return bitwarden_source(load_csv(text), args)
+    return bitwarden_source(parse_json(text, file, args), args)
+
+
+def load_keepass(data, file, args):
+    password = getpass.getpass("Database password: ")
+    try:
+        root = keepass.open_database(data, password, args.keyfile)
+    except keepass.KeePassError as e:
+        print(f"Error: Couldn't open {file}: {e}")
+        exit()
+    return keepass.KeePassSource(root)
+
+
+def bitwarden_source(js, args):
+    if args.attachments_dir:
+        attach_files(js, load_attachments_dir(args.attachments_dir))
+    return sources.BitwardenSource(js)
+
+
 def print_yml(file):
     cfg = pprint.pformat(json)
     logging.debug(cfg)
 
 
+def category_rule(value):
+    folder, _, category = value.partition('=')
+    if not folder or not category:
+        raise argparse.ArgumentTypeError(f"'{value}' should be "
+                                         "FOLDER=CATEGORY")
+    return folder, category
+
+
 def get_args():
     parser = argparse.ArgumentParser(description='Bitwarden to Moolitpass\n')
 
     parser.add_argument('-f', '--file', action='store', required=False,
                         default=None,
-                        help='Bitwarden exported json file')
+                        help='Bitwarden exported json, csv or zip file, a '
+                             'KeePass kdbx database, a 1Password 1pux archive '
+                             'or a Chrome, Edge or Firefox password csv, read '
+                             'from stdin when missing or "-"')
+
+    parser.add_argument('-o', '--output', action='store', required=False,
+                        default=None,
+                        help='Mooltipass csv file to write, defaults to '
+                             '<file>.csv and is required when reading stdin')
+
+    parser.add_argument('--format', action='store', required=False,
+                        default=None, choices=FORMATS,
+                        help='Format of the exported file, guessed from the '
+                             'file extension by default')
+
+    parser.add_argument('--keyfile', action='store', required=False,
+                        default=None,
+                        help='Key file of the KeePass database')
+
+    parser.add_argument('--serve', action='store', required=False,
+                        default=None, nargs='?', const=bwserve.DEFAULT_URL,
+                        metavar='URL',
+                        help='Fetch the vault from a local "bw serve" instead '
+                             f'of a file (default {bwserve.DEFAULT_URL})')
+
+    parser.add_argument('--server', action='store', required=False,
+                        default=None, metavar='URL',
+                        help='Log in to a Bitwarden or Vaultwarden server '
+                             'with --email and sync the vault from it')
+
+    parser.add_argument('--filter', '--include', action='append',
+                        required=False, default=[], dest='filter',
+                        type=selection.parse_rule, metavar='RULE',
+                        help='Only export items matching RULE, can be '
+                             'repeated: rules on the same key are '
+                             'alternatives, rules on different keys must all '
+                             f'match. {selection.RULES_HELP}')
+
+    parser.add_argument('-e', '--exclude', action='append', required=False,
+                        default=[], type=selection.parse_rule,
+                        metavar='RULE',
+                        help='Skip items matching RULE, can be repeated')
+
+    parser.add_argument('--unfoldered', action='store', required=False,
+                        default=selection.AUTO,
+                        choices=[selection.AUTO, selection.INCLUDE,
+                                 selection.EXCLUDE],
+                        help='Items without a folder: auto treats them as '
+                             f'the "{selection.NO_FOLDER}" folder, so a '
+                             'folder --filter drops them unless it names '
+                             f'"{selection.NO_FOLDER}", include keeps them '
+                             'whatever the folder rules say, exclude drops '
+                             'them (default auto)')
+
+    parser.add_argument('--trashed', action='store', required=False,
+                        default=selection.EXCLUDE,
+                        choices=[selection.INCLUDE, selection.EXCLUDE,
+                                 selection.ONLY],
+                        help='Items in the trash (default exclude)')
+
+    parser.add_argument('--organization', action='store', required=False,
+                        default=selection.INCLUDE,
+                        choices=[selection.INCLUDE, selection.EXCLUDE,
+                                 selection.ONLY],
+                        help='Items owned by an organization (default '
+                             'include)')
+
+    parser.add_argument('--split-by-collection', action='store_true',
+                        required=False, default=False,
+                        help='Write the credentials of each collection to '
+                             '<output>.<collection>.csv, items in several '
+                             'collections only go to one of them')
+
+    parser.add_argument('--preview', action='store_true', required=False,
+                        default=False,
+                        help='Only show how many items the rules select')
+
+    parser.add_argument('-r', '--recursive', action='store_true',
+                        required=False, default=False,
+                        help='Make --filter and --exclude cover the nested '
+                             'folders ("Parent/Child") too')
+
+    parser.add_argument('--list-folders', action='store_true', required=False,
+                        default=False,
+                        help='Show the folder tree with item counts and exit, '
+                             'counts follow --trashed and --organization')
+
+    parser.add_argument('--service-name', action='store', required=False,
+                        default=psl.DOMAIN,
+                        choices=[psl.DOMAIN, psl.HOST, psl.URI],
+                        help='How to turn URIs into service names: the '
+                             'registrable domain (www.example.co.uk gives '
+                             'example.co.uk), the full host or the URI as '
+                             'is. IP addresses and single label hosts keep '
+                             'their port. A Bitwarden match detection '
+                             'overrides this in every mode: domain gives the '
+                             'domain, host, starts with and exact keep the '
+                             'host, never URIs are skipped and regex URIs '
+                             'give the domain they name (default domain)')
+
+    parser.add_argument('--totp', action='store_true', required=False,
+                        default=False,
+                        help='Write the TOTP secrets of the exported '
+                             'credentials to <output>.totp.json for the '
+                             'Mooltipass BLE authenticator')
+
+    parser.add_argument('--field-rule', action='append', required=False,
+                        default=[], type=fields.parse_rule,
+                        metavar='NAME=TARGET',
+                        help='Map custom fields matching the NAME pattern to '
+                             'the credential description, a companion note, '
+                             'an extra credential or ignore them (TARGET is '
+                             f"one of {', '.join(fields.TARGETS)}), the first "
+                             'matching rule wins, can be repeated')
+
+    parser.add_argument('--favorites', action='store_true', required=False,
+                        default=False,
+                        help='Assign favorite items to the device favorite '
+                             'slots in <output>.favorites.json')
+
+    parser.add_argument('--favorite-slots', action='store', required=False,
+                        default=mooltipass.FAVORITE_SLOTS, type=int,
+                        help='Number of favorite slots on the device '
+                             f'(default {mooltipass.FAVORITE_SLOTS})')
+
+    parser.add_argument('--favorite-order', action='store', required=False,
+                        default='revision', choices=['revision', 'name'],
+                        help='Which favorites get a slot when there are too '
+                             'many, the most recently changed or by name')
+
+    parser.add_argument('--favorite', action='append', required=False,
+                        default=[], metavar='NAME',
+                        help='Item that gets a favorite slot before the '
+                             'others, in the order given, can be repeated')
+
+    parser.add_argument('--categories', action='store_true', required=False,
+                        default=False,
+                        help='Tag credentials with device categories in '
+                             '<output>.categories.json, the folders with '
+                             'the most credentials become the '
+                             f'{mooltipass.CATEGORY_COUNT} categories unless '
+                             '--category is given')
+
+    parser.add_argument('--category', action='append', required=False,
+                        default=[], type=category_rule,
+                        metavar='FOLDER=CATEGORY',
+                        help='Put the credentials of a folder in a named '
+                             'device category, can be repeated')
+
+    parser.add_argument('--notes', action='store_true', required=False,
+                        default=False,
+                        help='Write secure notes to <output>.notes.json as '
+                             'Mooltipass BLE notes')
+
+    parser.add_argument('--split-notes', action='store_true', required=False,
+                        default=False,
+                        help='Split notes longer than '
+                             f'{mooltipass.NOTE_MAX_LEN} characters into '
+                             'several device notes instead of skipping them')
+
+    parser.add_argument('--data-files', action='store_true', required=False,
+                        default=False,
+                        help='Write cards and identities to <output>.files/ '
+                             'as Mooltipass data files')
+
+    parser.add_argument('--attachments', action='store_true', required=False,
+                        default=False,
+                        help='Write attachments to <output>.files/ as '
+                             'Mooltipass data files')
+
+    parser.add_argument('--attachments-dir', action='store', required=False,
+                        default=None, metavar='DIR',
+                        help='Directory with decrypted attachments stored as '
+                             'DIR/<item id>/<file name>')
+
+    parser.add_argument('--max-file-size', action='store', required=False,
+                        default=mooltipass.DATA_FILE_MAX_SIZE, type=int,
+                        help='Largest data file the device takes, in bytes '
+                             f'(default {mooltipass.DATA_FILE_MAX_SIZE})')
+
+    parser.add_argument('--ssh-keys', action='store_true', required=False,
+                        default=False,
+                        help='Write SSH keys to <output>.files/ for '
+                             'moolticute-ssh-agent')
+
+    parser.add_argument('--ssh-passphrase', action='store_true',
+                        required=False, default=False,
+                        help='Ask for the passphrase of encrypted SSH keys, '
+                             'they are skipped otherwise')
 
-    parser.add_argument('--filter', action='store', required=False,
+    parser.add_argument('--email', action='store', required=False,
                         default=None,
-                        help='Filter out a single folder')
+                        help='Account email, needed for --server and account '
+                             'restricted encrypted exports')
 
-    parser.add_argument('-e', '--exclude', action='store', required=False,
+    parser.add_argument('--user-key', action='store', required=False,
                         default=None,
-                        help='Exclude a folder')
+                        help='Encrypted user key of the account (the "2.…" '
+                             'string from the bw CLI data.json or /api/sync)')
 
-    if len(sys.argv) < 2:
+    parser.add_argument('--kdf', action='store', required=False,
+                        default='pbkdf2', choices=KDF_TYPES.keys(),
+                        help='KDF configured for the account')
+
+    parser.add_argument('--kdf-iterations', action='store', required=False,
+                        default=None, type=int,
+                        help='KDF iterations configured for the account '
+                             '(default 600000 for pbkdf2, 3 for argon2id)')
+
+    parser.add_argument('--kdf-memory', action='store', required=False,
+                        default=64, type=int,
+                        help='Argon2id memory in MiB configured for the '
+                             'account')
+
+    parser.add_argument('--kdf-parallelism', action='store', required=False,
+                        default=4, type=int,
+                        help='Argon2id parallelism configured for the account')
+
+    if len(sys.argv) < 2 and sys.stdin.isatty():
         parser.print_help()
         sys.exit(1)
 
-    return parser.parse_args()
+    args = parser.parse_args()
+    if args.kdf_iterations is None:
+        args.kdf_iterations = KDF_ITERATIONS[args.kdf]
+    return args
+
+
+def open_source(args):
+    if args.serve:
+        try:
+            js = bwserve.load_serve(args.serve,
+                                    args.trashed != selection.EXCLUDE)
+        except bwserve.ServeError as e:
+            print(f"Error: bw serve: {e}")
+            exit()
+    elif args.server:
+        if not args.email:
+            print("Error: Need --email to log in to the server")
+            exit()
+
+        password = getpass.getpass("Master password: ")
+        try:
+            js, skipped = bwserver.load_server(args.server, args.email,
+                                               password)
+        except (bwserver.ServerError, bwcrypto.DecryptionError) as e:
+            print(f"Error: {args.server}: {e}")
+            exit()
+        for c in skipped:
+            print(f"Item {c['id']} skipped, no key for organization "
+                  f"{c['organizationId']}")
+    else:
+        return load_vault(args.file, args)
 
+    return bitwarden_source(js, args)
 
-def folder_name_to_id(js, name):
-    for f in js['folders']:
-        if f['name'] == name:
-            return f['id']
+
+def list_folders(source, trashed, organization):
+    counts = {}
+    for e in source.entries():
+        # Count what an export with the same switches would get
+        if not selection.group_allowed(e.trashed, trashed):
+            continue
+        if not selection.group_allowed(e.organization is not None,
+                                       organization):
+            continue
+        counts[e.folder_id] = counts.get(e.folder_id, 0) + 1
+
+    # Parents don't have to exist as folders of their own
+    tree = {}
+    for f in source.folders():
+        parts = f.name.split('/')
+        for n in range(1, len(parts) + 1):
+            tree.setdefault('/'.join(parts[:n]), 0)
+        tree[f.name] += counts.get(f.id, 0)
+
+    for path in sorted(tree, key=lambda p: p.split('/')):
+        total = sum(c for p, c in tree.items()
+                    if p == path or p.startswith(f"{path}/"))
+        name = path.rsplit('/', 1)[-1]
+        line = f"{'  ' * path.count('/')}{name} ({tree[path]} items"
+        if total != tree[path]:
+            line += f", {total} with subfolders"
+        print(f"{line})")
+    print(f"{selection.NO_FOLDER} ({counts.get(None, 0)} items)")
 
 
 def main():
     print("Bitwarden to Mooltipass")
     args = get_args()
 
-    if not args.file:
-        print("Error: Need a json file from Bitwarden")
+    output = args.output
+    if output is None and not (args.list_folders or args.preview):
+        if args.serve or args.server or from_stdin(args.file):
+            print("Error: Need --output when not reading an export file")
+            exit()
+        output = f"{args.file}.csv"
+
+    source = open_source(args)
+
+    if args.list_folders:
+        list_folders(source, args.trashed, args.organization)
+        exit()
+
+    try:
+        rules = selection.Selection(source, args.filter, args.exclude,
+                                    args.recursive, args.unfoldered,
+                                    args.trashed, args.organization)
+    except selection.SelectionError as e:
+        print(f"Error: {e}")
         exit()
 
-    js = load_json(args.file)
+    everything = list(source.entries())
+    selected = []
+    seen = set()
+    duplicates = 0
+    for e in everything:
+        if not rules.selected(e):
+            continue
+        # The same item shows up once per collection in some exports
+        if e.id is not None and e.id in seen:
+            duplicates += 1
+            continue
+        seen.add(e.id)
+        selected.append(e)
 
-    folder_id = None
-    if args.filter:
-        folder_id = folder_name_to_id(js, args.filter)
+    if duplicates:
+        print(f"Skipped {duplicates} duplicate items")
 
-    exclude = None
-    if args.exclude:
-        exclude = folder_name_to_id(js, args.exclude)
+    print(f"Selected {len(selected)} of {len(everything)} items")
+    for t in selection.TYPES:
+        count = len([e for e in selected if e.type == t])
+        if count:
+            print(f"  {t}: {count}")
+    if args.preview:
+        exit()
+
+    entries = [e for e in selected if e.type == sources.LOGIN]
+    never = 0
+    for e in entries:
+        uris = []
+        for uri in e.uris:
+            try:
+                uri.service = psl.match_service(uri, args.service_name)
+            except psl.ServiceError as err:
+                print(f"URI needs manual handling: {e.name}: {err}")
+                continue
+            if uri.service is None:
+                never += 1
+                continue
+            uris.append(uri)
+        e.uris = uris
+    if never:
+        print(f"{never} URIs with match detection 'never' skipped")
+
+    extra, companion_notes, unmapped = fields.apply_rules(entries,
+                                                          args.field_rule)
+    entries += extra
+    if unmapped and not args.field_rule:
+        print(f"{len(unmapped)} custom fields skipped, use --field-rule to "
+              "convert them")
+    else:
+        for e, f, reason in unmapped:
+            print(f"Custom field not converted: {e.name}/{f.name}: {reason}")
+
+    if args.split_by_collection:
+        collection_names = {c.id: c.name for c in source.collections()}
+        groups, shared = mooltipass.split_by_collection(
+            entries, collection_names, rules.included_collections())
+
+        mooltipass.write_credentials(groups.pop(None, []), output)
+        used = set()
+        for name, group in sorted(groups.items()):
+            path = mooltipass.sidecar(
+                output, f".{mooltipass.file_name(name, used)}.csv")
+            print(f"Collection {name}: {path}")
+            mooltipass.write_credentials(group, path)
+        for e, names in shared:
+            print(f"{e.name} is in {', '.join(names)}, written to {names[0]}")
+    else:
+        mooltipass.write_credentials(entries, output)
 
-    with open(f"{args.file}.csv", 'w') as csv:
-        for i in js['items']:
-            folderid = i.get('folderId', None)
-            if folderid and exclude:
-                if folderid in exclude:
-                    # print(f"Excluding {args.exclude}/{exclude}")
-                    continue
+    favorite_count = len([e for e in entries if e.favorite])
+    if favorite_count and not args.favorites:
+        print(f"{favorite_count} favorites not assigned, use --favorites to "
+              "map them to device slots")
+    elif args.favorites:
+        records, skipped, missing = mooltipass.favorites(
+            entries, args.favorite_slots, args.favorite_order, args.favorite)
+        mooltipass.write_json(records,
+                              mooltipass.sidecar(output, '.favorites.json'))
+        print(f"Assigned {len(records)} favorite slots")
+        for e, reason in skipped:
+            print(f"Favorite not assigned: {e.name}: {reason}")
+        for name in missing:
+            print(f"Favorite not found: {name}")
 
-            login = i.get('login', None)
-            if login is None:
-                # print(f"Couldn't find login for: {i}")
+    if args.categories or args.category:
+        folder_names = {f.id: f.name for f in source.folders()}
+        try:
+            mapping, unmapped = mooltipass.categories(entries, folder_names,
+                                                      dict(args.category))
+        except ValueError as e:
+            print(f"Error: {e}")
+            exit()
+        mooltipass.write_json(mapping,
+                              mooltipass.sidecar(output, '.categories.json'))
+        for c in mapping['categories']:
+            print(f"Category {c['id']}: {c['name']}")
+        for name in unmapped:
+            print(f"Folder without category: {name}")
+
+    with_totp = [e for e in entries if e.totp]
+    if with_totp and not args.totp:
+        print(f"{len(with_totp)} credentials have TOTP secrets, use --totp to "
+              "migrate them")
+    elif args.totp:
+        records, failed = mooltipass.totp_credentials(entries)
+        path = mooltipass.sidecar(output, '.totp.json')
+        mooltipass.write_json(records, path)
+        print(f"Migrated TOTP secrets for {len(records)} credentials")
+        for e, reason in failed:
+            print(f"TOTP not migrated: {e.name}: {reason}")
+
+    secure_notes = [e for e in selected if e.type == sources.NOTE]
+    if secure_notes and not args.notes:
+        print(f"{len(secure_notes)} secure notes skipped, use --notes to "
+              "convert them")
+        secure_notes = []
+
+    secure_notes += companion_notes
+    if args.notes or secure_notes:
+        records, failed = mooltipass.notes(secure_notes, args.split_notes)
+        mooltipass.write_json(records, mooltipass.sidecar(output,
+                                                          '.notes.json'))
+        print(f"Converted {len(records)} notes")
+        for e, reason in failed:
+            print(f"Note too long, use --split-notes: {e.name}: {reason}")
+
+    used_files = {sshkeys.DATA_FILE.lower()}
+    wallet = [e for e in selected if e.type in (sources.CARD,
+                                                sources.IDENTITY)]
+    if wallet and not args.data_files:
+        print(f"{len(wallet)} cards and identities skipped, use --data-files "
+              "to store them")
+    elif args.data_files:
+        files, failed = mooltipass.data_files(wallet, args.max_file_size,
+                                              used_files)
+        mooltipass.write_files(files, mooltipass.sidecar(output, '.files'))
+        for name, _, e, stored in files:
+            print(f"Stored {e.type} {e.name} as {name}: {', '.join(stored)}")
+        for e, reason in failed:
+            print(f"{e.type.capitalize()} not stored: {e.name}: {reason}")
+
+    ssh_keys = [e for e in selected if e.type == sources.SSH_KEY]
+    if ssh_keys and not args.ssh_keys:
+        print(f"{len(ssh_keys)} SSH keys skipped, use --ssh-keys to convert "
+              "them")
+    elif args.ssh_keys:
+        passphrase = None
+        if args.ssh_passphrase:
+            passphrase = getpass.getpass("SSH key passphrase: ")
+
+        keys = []
+        for e in ssh_keys:
+            try:
+                key, fingerprint = sshkeys.convert(e.details, passphrase)
+            except sshkeys.SshKeyError as err:
+                print(f"SSH key not converted: {e.name}: {err}")
                 continue
+            keys.append(key)
+            print(f"Converted SSH key {e.name} ({fingerprint})")
+
+        data = sshkeys.keys_file(keys)
+        if keys and len(data) > args.max_file_size:
+            # All keys share one data file, there is no storing some of them
+            print(f"SSH keys not stored: {sshkeys.DATA_FILE}: {len(data)} "
+                  f"bytes, the device takes {args.max_file_size}")
+        elif keys:
+            mooltipass.write_files([(sshkeys.DATA_FILE, data, None, [])],
+                                   mooltipass.sidecar(output, '.files'))
 
-            username = login['username']
-            password = login['password']
-
-            for uri in login['uris']:
-                item = f"{uri['uri']},{username},{password}"
-                if folder_id:
-                    if folder_id in i['folderId']:
-                        print(item)
-                        csv.write(f"{item}\n")
-                else:
-                    print(item)
-                    csv.write(f"{item}\n")
+    with_attachments = [e for e in selected if e.attachments]
+    if with_attachments and not args.attachments:
+        count = sum(len(e.attachments) for e in with_attachments)
+        print(f"{count} attachments skipped, use --attachments to store them")
+    elif args.attachments:
+        files, failed = mooltipass.attachment_files(with_attachments,
+                                                    args.max_file_size,
+                                                    used_files)
+        mooltipass.write_files(files, mooltipass.sidecar(output, '.files'))
+        for name, data, e, stored in files:
+            print(f"Stored attachment {e.name}/{stored[0]} as {name} "
+                  f"({len(data)} bytes)")
+        for e, a, reason in failed:
+            print(f"Attachment not stored: {e.name}/{a.name}: {reason}")
 
 
 if __name__ == "__main__":
@@ -0,0 +1,87 @@
+import argparse
+import dataclasses
+import fnmatch
+
+import sources
+
+DESCRIPTION = 'description'
+NOTE = 'note'
+CREDENTIAL = 'credential'
+IGNORE = 'ignore'
+
+TARGETS = (DESCRIPTION, NOTE, CREDENTIAL, IGNORE)
+
+
+@dataclasses.dataclass
+class Rule:
+    pattern: str
+    target: str
+
+
+def parse_rule(value):
+    pattern, _, target = value.rpartition('=')
+    if not pattern or target not in TARGETS:
+        raise argparse.ArgumentTypeError(
+            f"'{value}' should be NAME=TARGET with TARGET one of "
+            f"{', '.join(TARGETS)}")
+    return Rule(pattern, target)
+
+
+def rule_target(rules, name):
+    for r in rules:
+        if fnmatch.fnmatchcase(name.lower(), r.pattern.lower()):
+            return r.target
+
+
+def field_value(entry, f):
+    if f.type == sources.FIELD_LINKED:
+        if f.linked == 'username':
+            return entry.username
+        if f.linked == 'password':
+            return entry.password
+        return None
+    if f.type == sources.FIELD_BOOLEAN:
+        return 'true' if str(f.value).lower() == 'true' else 'false'
+    return f.value
+
+
+def apply_rules(entries, rules):
+    credentials = []
+    notes = []
+    unmapped = []
+    for e in entries:
+        description = []
+        note = []
+        for f in e.fields:
+            name = f.name or ''
+            target = rule_target(rules, name)
+            if target is None:
+                unmapped.append((e, f, "No matching --field-rule"))
+                continue
+            if target == IGNORE:
+                continue
+
+            value = field_value(e, f)
+            if value is None:
+                unmapped.append((e, f, "Linked field can't be resolved"))
+                continue
+
+            if target == DESCRIPTION:
+                description.append(f"{name}: {value}")
+            elif target == NOTE:
+                note.append(f"{name}: {value}")
+            else:
+                credentials.append(dataclasses.replace(
+                    e, name=f"{e.name} {name}", username=name,
+                    password=value, totp=None, fields=[], description=None,
+                    favorite=False))
+
+        if description:
+            e.description = '; '.join(description)
+        if note:
+            notes.append(sources.Entry(name=f"{e.name} fields",
+                                       type=sources.NOTE,
+                                       folder_id=e.folder_id,
+                                       notes='\n'.join(note)))
+
+    return credentials, notes, unmapped
@@ -0,0 +1,306 @@
+import base64
+import gzip
+import hashlib
+import hmac
+import io
+import struct
+import uuid
+import xml.etree.ElementTree as ET
+import zlib
+
+from cryptography.hazmat.primitives import padding
+from cryptography.hazmat.primitives.ciphers import Cipher, algorithms, modes
+from cryptography.hazmat.primitives.kdf.argon2 import Argon2d, Argon2id
+
+from sources import (FIELD_HIDDEN, FIELD_TEXT, LOGIN, CustomField, Entry,
+                     Folder, Source, Uri)
+
+SIGNATURE = b'\x03\xd9\xa2\x9a\x67\xfb\x4b\xb5'
+
+HEADER_END = 0
+HEADER_CIPHER_ID = 2
+HEADER_COMPRESSION = 3
+HEADER_MASTER_SEED = 4
+HEADER_ENCRYPTION_IV = 7
+HEADER_KDF_PARAMETERS = 11
+
+INNER_HEADER_END = 0
+INNER_HEADER_STREAM_ID = 1
+INNER_HEADER_STREAM_KEY = 2
+
+CIPHER_AES256 = uuid.UUID('31c1f2e6-bf71-4350-be58-05216afc5aff')
+CIPHER_CHACHA20 = uuid.UUID('d6038a2b-8b6f-4cb5-a524-339a31dbb59a')
+
+KDF_AES = uuid.UUID('c9d9f39a-628a-4460-bf74-0d08c18a4fea')
+KDF_ARGON2D = uuid.UUID('ef636ddf-8c29-444b-91f7-a9a403e30a0c')
+KDF_ARGON2ID = uuid.UUID('9e298b19-56db-4773-b23d-fc3ec6f0a1e6')
+
+ARGON2_VERSION = 0x13
+
+STREAM_CHACHA20 = 3
+
+STANDARD_STRINGS = ('Title', 'UserName', 'Password', 'URL', 'Notes', 'otp')
+
+
+class KeePassError(Exception):
+    pass
+
+
+def read_variant_dictionary(data):
+    values = {}
+    stream = io.BytesIO(data)
+    stream.read(2)  # version
+    while True:
+        kind = stream.read(1)[0]
+        if kind == 0:
+            return values
+        key_len, = struct.unpack('<I', stream.read(4))
+        key = stream.read(key_len).decode('utf-8')
+        value_len, = struct.unpack('<I', stream.read(4))
+        value = stream.read(value_len)
+        if kind in (0x04, 0x05):
+            value = int.from_bytes(value, 'little')
+        elif kind in (0x0c, 0x0d):
+            value = int.from_bytes(value, 'little', signed=True)
+        elif kind == 0x08:
+            value = value != b'\x00'
+        elif kind == 0x18:
+            value = value.decode('utf-8')
+        values[key] = value
+
+
+def read_header(stream):
+    if stream.read(8) != SIGNATURE:
+        raise KeePassError("Not a KeePass database")
+    _, major = struct.unpack('<HH', stream.read(4))
+    if major != 4:
+        raise KeePassError(f"KDBX {major} is not supported, only KDBX 4")
+
+    fields = {}
+    while True:
+        field_id = stream.read(1)[0]
+        size, = struct.unpack('<I', stream.read(4))
+        data = stream.read(size)
+        if field_id == HEADER_END:
+            return fields
+        fields[field_id] = data
+
+
+def read_keyfile(keyfile):
+    try:
+        with open(keyfile, 'rb') as k:
+            data = k.read()
+    except OSError as e:
+        raise KeePassError(f"Can't read keyfile: {e}")
+
+    if data.lstrip().startswith(b'<?xml'):
+        xml = ET.fromstring(data)
+        key = xml.findtext('Key/Data')
+        if key is None:
+            raise KeePassError("No key in keyfile")
+        if xml.findtext('Meta/Version', '1.0').startswith('2'):
+            return bytes.fromhex(''.join(key.split()))
+        return base64.b64decode(key)
+    if len(data) == 32:
+        return data
+    if len(data) == 64:
+        try:
+            return bytes.fromhex(data.decode('ascii'))
+        except ValueError:
+            pass
+    return hashlib.sha256(data).digest()
+
+
+def composite_key(password, keyfile):
+    parts = b''
+    if password:
+        parts += hashlib.sha256(password.encode('utf-8')).digest()
+    if keyfile:
+        parts += read_keyfile(keyfile)
+    return hashlib.sha256(parts).digest()
+
+
+def transform_key(key, params):
+    kdf = uuid.UUID(bytes=params['$UUID'])
+    if kdf == KDF_AES:
+        aes = algorithms.AES(params['S'])
+        encryptor = Cipher(aes, modes.ECB()).encryptor()
+        for _ in range(params['R']):
+            key = encryptor.update(key)
+        return hashlib.sha256(key).digest()
+
+    if kdf in (KDF_ARGON2D, KDF_ARGON2ID):
+        if params.get('V', ARGON2_VERSION) != ARGON2_VERSION:
+            raise KeePassError(f"Unsupported Argon2 version {params['V']}")
+        argon = Argon2d if kdf == KDF_ARGON2D else Argon2id
+        return argon(salt=params['S'], length=32, iterations=params['I'],
+                     lanes=params['P'], memory_cost=params['M'] // 1024,
+                     secret=params.get('K'),
+                     ad=params.get('A')).derive(key)
+
+    raise KeePassError(f"Unsupported KDF {kdf}")
+
+
+def read_blocks(stream, hmac_key):
+    payload = b''
+    index = 0
+    while True:
+        mac = stream.read(32)
+        size_bytes = stream.read(4)
+        size, = struct.unpack('<i', size_bytes)
+        data = stream.read(size)
+
+        block_index = struct.pack('<Q', index)
+        block_key = hashlib.sha512(block_index + hmac_key).digest()
+        expected = hmac.new(block_key, block_index + size_bytes + data,
+                            hashlib.sha256).digest()
+        if not hmac.compare_digest(mac, expected):
+            raise KeePassError(f"Block {index} is corrupted")
+
+        if size == 0:
+            return payload
+        payload += data
+        index += 1
+
+
+def decrypt_payload(cipher_id, key, iv, data):
+    if cipher_id == CIPHER_AES256:
+        decryptor = Cipher(algorithms.AES(key), modes.CBC(iv)).decryptor()
+        padded = decryptor.update(data) + decryptor.finalize()
+        unpadder = padding.PKCS7(128).unpadder()
+        return unpadder.update(padded) + unpadder.finalize()
+    if cipher_id == CIPHER_CHACHA20:
+        # The 16 byte nonce is a zero block counter followed by the IV
+        nonce = b'\x00' * 4 + iv
+        decryptor = Cipher(algorithms.ChaCha20(key, nonce), None).decryptor()
+        return decryptor.update(data)
+    raise KeePassError(f"Unsupported cipher {cipher_id}")
+
+
+def read_inner_header(stream):
+    fields = {}
+    while True:
+        field_id = stream.read(1)[0]
+        size, = struct.unpack('<I', stream.read(4))
+        data = stream.read(size)
+        if field_id == INNER_HEADER_END:
+            return fields
+        fields.setdefault(field_id, data)
+
+
+def unprotect(root, stream_id, stream_key):
+    if stream_id != STREAM_CHACHA20:
+        raise KeePassError(f"Unsupported inner stream cipher {stream_id}")
+
+    digest = hashlib.sha512(stream_key).digest()
+    nonce = b'\x00' * 4 + digest[32:44]
+    stream = Cipher(algorithms.ChaCha20(digest[:32], nonce), None).decryptor()
+
+    # The key stream runs over every protected value in document order
+    for value in root.iter('Value'):
+        if value.get('Protected') == 'True' and value.text:
+            data = stream.update(base64.b64decode(value.text))
+            value.text = data.decode('utf-8')
+
+
+def open_database(data, password, keyfile=None):
+    try:
+        return read_database(data, password, keyfile)
+    except (IndexError, KeyError, ValueError, EOFError, struct.error,
+            zlib.error, gzip.BadGzipFile, ET.ParseError):
+        raise KeePassError("Database or keyfile is damaged or truncated")
+
+
+def read_database(data, password, keyfile):
+    stream = io.BytesIO(data)
+    fields = read_header(stream)
+    header = data[:stream.tell()]
+
+    if hashlib.sha256(header).digest() != stream.read(32):
+        raise KeePassError("Header is corrupted")
+
+    params = read_variant_dictionary(fields[HEADER_KDF_PARAMETERS])
+    transformed = transform_key(composite_key(password, keyfile), params)
+    seed = fields[HEADER_MASTER_SEED]
+    hmac_key = hashlib.sha512(seed + transformed + b'\x01').digest()
+
+    header_key = hashlib.sha512(b'\xff' * 8 + hmac_key).digest()
+    expected = hmac.new(header_key, header, hashlib.sha256).digest()
+    if not hmac.compare_digest(stream.read(32), expected):
+        raise KeePassError("Wrong password or keyfile")
+
+    payload = decrypt_payload(uuid.UUID(bytes=fields[HEADER_CIPHER_ID]),
+                              hashlib.sha256(seed + transformed).digest(),
+                              fields[HEADER_ENCRYPTION_IV],
+                              read_blocks(stream, hmac_key))
+    if int.from_bytes(fields.get(HEADER_COMPRESSION, b'\x00'), 'little'):
+        payload = gzip.decompress(payload)
+
+    inner = io.BytesIO(payload)
+    inner_fields = read_inner_header(inner)
+    root = ET.fromstring(inner.read())
+    stream_id = int.from_bytes(inner_fields[INNER_HEADER_STREAM_ID], 'little')
+    unprotect(root, stream_id, inner_fields[INNER_HEADER_STREAM_KEY])
+    return root
+
+
+def entry_strings(entry):
+    return {s.findtext('Key'): s.findtext('Value') or ''
+            for s in entry.findall('String')}
+
+
+def entry_fields(entry):
+    fields = []
+    for s in entry.findall('String'):
+        if s.findtext('Key') in STANDARD_STRINGS:
+            continue
+        protected = s.find('Value').get('Protected') == 'True'
+        fields.append(CustomField(s.findtext('Key'), s.findtext('Value') or '',
+                                  FIELD_HIDDEN if protected else FIELD_TEXT))
+    return fields
+
+
+class KeePassSource(Source):
+    def __init__(self, root):
+        self.root = root
+        self._folders = []
+        self._entries = []
+
+        self.recycle_bin = root.findtext('Meta/RecycleBinUUID')
+        top = root.find('Root/Group')
+        if top is not None:
+            self.walk(top, None, False)
+
+    def walk(self, group, path, trashed):
+        group_id = None
+        if path is not None:
+            group_id = group.findtext('UUID')
+            self._folders.append(Folder(group_id, path))
+
+        # History entries live below Entry/History and aren't direct children
+        for entry in group.findall('Entry'):
+            strings = entry_strings(entry)
+            url = strings.get('URL')
+            self._entries.append(Entry(
+                name=strings.get('Title'),
+                type=LOGIN,
+                folder_id=group_id,
+                username=strings.get('UserName'),
+                password=strings.get('Password'),
+                uris=[Uri(url)] if url else [],
+                totp=strings.get('otp'),
+                notes=strings.get('Notes'),
+                fields=entry_fields(entry),
+                trashed=trashed,
+            ))
+
+        for child in group.findall('Group'):
+            name = child.findtext('Name')
+            self.walk(child, name if path is None else f"{path}/{name}",
+                      trashed or child.findtext('UUID') == self.recycle_bin)
+
+    def folders(self):
+        return self._folders
+
+    def entries(self):
+        return self._entries
@@ -0,0 +1,307 @@
+import csv
+import json
+import os
+import sys
+
+import sources
+import totp
+
+DESCRIPTION_MAX_LEN = 24
+
+FAVORITE_SLOTS = 50
+
+CATEGORY_COUNT = 4
+CATEGORY_NAME_MAX_LEN = 32
+
+# Stands in for items without a name
+UNTITLED = 'Untitled'
+
+NOTE_TITLE_MAX_LEN = 64
+NOTE_MAX_LEN = 512
+
+DATA_FILE_NAME_MAX_LEN = 64
+DATA_FILE_MAX_SIZE = 64 * 1024
+
+CARD_LABELS = {
+    'cardholderName': 'Cardholder name',
+    'brand': 'Brand',
+    'number': 'Number',
+    'expMonth': 'Expiration month',
+    'expYear': 'Expiration year',
+    'code': 'Security code',
+}
+
+IDENTITY_LABELS = {
+    'title': 'Title',
+    'firstName': 'First name',
+    'middleName': 'Middle name',
+    'lastName': 'Last name',
+    'username': 'Username',
+    'company': 'Company',
+    'email': 'Email',
+    'phone': 'Phone',
+    'address1': 'Address 1',
+    'address2': 'Address 2',
+    'address3': 'Address 3',
+    'city': 'City',
+    'state': 'State',
+    'postalCode': 'Postal code',
+    'country': 'Country',
+    'ssn': 'Social security number',
+    'passportNumber': 'Passport number',
+    'licenseNumber': 'License number',
+}
+
+
+def sidecar(output, suffix):
+    return f"{os.path.splitext(output)[0]}{suffix}"
+
+
+def credentials(entries):
+    for e in entries:
+        # Several URIs often boil down to the same service
+        services = []
+        for uri in e.uris:
+            service = uri.service or uri.uri
+            if service not in services:
+                services.append(service)
+        for service in services:
+            yield service, e
+
+
+def split_by_collection(entries, collection_names, preferred):
+    groups = {}
+    shared = []
+    for e in entries:
+        ids = [c for c in e.collections if c in collection_names]
+        if not ids:
+            groups.setdefault(None, []).append(e)
+            continue
+
+        # An item goes to one file only, wanted collections win over the rest
+        ids.sort(key=lambda c: (c not in preferred, collection_names[c]))
+        name = collection_names[ids[0]]
+        groups.setdefault(name, []).append(e)
+        if len(ids) > 1:
+            shared.append((e, [collection_names[c] for c in ids]))
+    return groups, shared
+
+
+def write_credentials(entries, output):
+    # Only add the description column when there is something to put in it
+    described = any(e.description for e in entries)
+    for e in entries:
+        if e.description and len(e.description) > DESCRIPTION_MAX_LEN:
+            print(f"Description truncated to {DESCRIPTION_MAX_LEN} "
+                  f"characters: {e.name}")
+
+    # Quote commas in passwords and descriptions instead of adding columns
+    echo = csv.writer(sys.stdout, lineterminator='\n')
+    with open(output, 'w', newline='') as out:
+        writer = csv.writer(out, lineterminator='\n')
+        for service, e in credentials(entries):
+            row = [service, e.username, e.password]
+            if described:
+                row.append((e.description or '')[:DESCRIPTION_MAX_LEN])
+            echo.writerow(row)
+            writer.writerow(row)
+
+
+def totp_credentials(entries):
+    records = []
+    failed = []
+    for e in entries:
+        if not e.totp:
+            continue
+
+        try:
+            t = totp.parse(e.totp)
+            totp.check(t)
+        except totp.TotpError as err:
+            failed.append((e, err))
+            continue
+
+        services = [s for s, _ in credentials([e])]
+        if not services:
+            failed.append((e, "No URI to attach the secret to"))
+
+        # Same keys Moolticute uses for a credential's TOTP settings
+        for service in services:
+            records.append({
+                'service': service,
+                'login': e.username,
+                'totp': {
+                    'totp_secret_key': t.base32(),
+                    'totp_time_step': t.period,
+                    'totp_code_size': t.digits,
+                },
+            })
+
+    return records, failed
+
+
+def favorites(entries, slots, order, names):
+    by_name = {}
+    for e in entries:
+        by_name.setdefault(e.name, e)
+
+    # Explicitly listed items come first, in the given order
+    ranked = [by_name[n] for n in names if n in by_name]
+    missing = [n for n in names if n not in by_name]
+
+    rest = [e for e in entries if e.favorite and e not in ranked]
+    if order == 'name':
+        rest.sort(key=lambda e: (e.name or '').lower())
+    else:
+        # Most recently changed first, items without a date last
+        rest.sort(key=lambda e: e.revision or '', reverse=True)
+    ranked += rest
+
+    records = []
+    skipped = []
+    for e in ranked:
+        services = [s for s, _ in credentials([e])]
+        if not services:
+            skipped.append((e, "No URI to use as service"))
+        elif len(records) >= slots:
+            skipped.append((e, f"All {slots} slots are taken"))
+        else:
+            records.append({'slot': len(records), 'service': services[0],
+                            'login': e.username})
+    return records, skipped, missing
+
+
+def categories(entries, folder_names, mapping):
+    counts = {}
+    for e in entries:
+        if e.folder_id in folder_names:
+            name = folder_names[e.folder_id]
+            counts[name] = counts.get(name, 0) + 1
+
+    if not mapping:
+        # Busiest folders get the few categories there are
+        busiest = sorted(counts, key=lambda n: (-counts[n], n))
+        mapping = {n: n for n in busiest[:CATEGORY_COUNT]}
+
+    names = []
+    for category in mapping.values():
+        if category not in names:
+            names.append(category)
+    if len(names) > CATEGORY_COUNT:
+        raise ValueError(f"{len(names)} categories given, the device has "
+                         f"{CATEGORY_COUNT}")
+
+    ids = {n: i for i, n in enumerate(names, 1)}
+    records = []
+    for service, e in credentials(entries):
+        category = mapping.get(folder_names.get(e.folder_id))
+        e.category = ids.get(category, 0)
+        records.append({'service': service, 'login': e.username,
+                        'category': e.category})
+
+    unmapped = [n for n in counts if n not in mapping]
+    return {
+        'categories': [{'id': i, 'name': n[:CATEGORY_NAME_MAX_LEN]}
+                       for n, i in ids.items()],
+        'credentials': records,
+    }, unmapped
+
+
+def split_note(text):
+    parts = []
+    while len(text) > NOTE_MAX_LEN:
+        # Prefer cutting at a line break so parts stay readable
+        cut = text.rfind('\n', 0, NOTE_MAX_LEN) + 1 or NOTE_MAX_LEN
+        parts.append(text[:cut])
+        text = text[cut:]
+    return parts + [text]
+
+
+def notes(entries, split):
+    records = []
+    failed = []
+    for e in entries:
+        text = e.notes or ''
+        if len(text) > NOTE_MAX_LEN and not split:
+            failed.append((e, f"{len(text)} characters, the device holds "
+                              f"{NOTE_MAX_LEN}"))
+            continue
+
+        parts = split_note(text)
+        for n, part in enumerate(parts, 1):
+            suffix = f" ({n}/{len(parts)})" if len(parts) > 1 else ''
+            name = e.name or UNTITLED
+            title = name[:NOTE_TITLE_MAX_LEN - len(suffix)] + suffix
+            records.append({'title': title, 'content': part})
+
+    return records, failed
+
+
+def file_name(name, used):
+    name = ''.join(c if c.isalnum() or c in ' ._-()' else '_' for c in name)
+    name = name.strip() or 'unnamed'
+    candidate = name[:DATA_FILE_NAME_MAX_LEN]
+    n = 2
+    while candidate.lower() in used:
+        suffix = f" ({n})"
+        candidate = name[:DATA_FILE_NAME_MAX_LEN - len(suffix)] + suffix
+        n += 1
+    used.add(candidate.lower())
+    return candidate
+
+
+def data_files(entries, max_size, used):
+    files = []
+    failed = []
+    for e in entries:
+        labels = CARD_LABELS if e.type == sources.CARD else IDENTITY_LABELS
+        name = e.name or UNTITLED
+        lines = [f"Name: {name}"]
+        stored = []
+        for key, value in (e.details or {}).items():
+            if value in (None, ''):
+                continue
+            label = labels.get(key, key)
+            lines.append(f"{label}: {value}")
+            stored.append(label)
+        if e.notes:
+            lines.append(f"Notes:\n{e.notes}")
+            stored.append('Notes')
+
+        data = ('\n'.join(lines) + '\n').encode('utf-8')
+        if len(data) > max_size:
+            failed.append((e, f"{len(data)} bytes, the device takes "
+                              f"{max_size}"))
+            continue
+        files.append((file_name(name, used), data, e, stored))
+    return files, failed
+
+
+def attachment_files(entries, max_size, used):
+    files = []
+    failed = []
+    for e in entries:
+        for a in e.attachments:
+            if a.data is None:
+                failed.append((e, a, "Not in the export, use a ZIP export or "
+                                     "--attachments-dir"))
+                continue
+            if len(a.data) > max_size:
+                failed.append((e, a, f"{len(a.data)} bytes, the device takes "
+                                     f"{max_size}"))
+                continue
+            files.append((file_name(f"{e.name} - {a.name}", used), a.data, e,
+                          [a.name]))
+    return files, failed
+
+
+def write_files(files, directory):
+    os.makedirs(directory, exist_ok=True)
+    for name, data, _, _ in files:
+        with open(os.path.join(directory, name), 'wb') as out:
+            out.write(data)
+
+
+def write_json(records, output):
+    with open(output, 'w') as out:
+        json.dump(records, out, indent=4)
@@ -0,0 +1,123 @@
+import datetime
+import json
+import zipfile
+
+from sources import (CARD, IDENTITY, LOGIN, NOTE, SSH_KEY, Entry, Folder,
+                     Source, Uri)
+
+EXPORT_DATA = 'export.data'
+
+CATEGORIES = {
+    '001': LOGIN,
+    '002': CARD,
+    '003': NOTE,
+    '004': IDENTITY,
+    '005': LOGIN,  # Password
+    '114': SSH_KEY,
+}
+
+
+class OnePasswordError(Exception):
+    pass
+
+
+def login_field(details, designation):
+    for f in details.get('loginFields', []):
+        if f.get('designation') == designation:
+            return f.get('value')
+
+
+def item_totp(details):
+    for section in details.get('sections', []):
+        for f in section.get('fields', []):
+            value = f.get('value') or {}
+            if value.get('totp'):
+                return value['totp']
+
+
+def ssh_key(value):
+    # Same keys as a Bitwarden sshKey so sshkeys.convert takes it
+    metadata = value.get('metadata') or {}
+    return {
+        'privateKey': value.get('privateKey') or metadata.get('privateKey'),
+        'publicKey': metadata.get('publicKey'),
+        'keyFingerprint': metadata.get('fingerprint'),
+    }
+
+
+def item_details(details):
+    values = {}
+    for section in details.get('sections', []):
+        for f in section.get('fields', []):
+            value = f.get('value') or {}
+            # Each value is a single entry keyed by its kind
+            for kind, v in value.items():
+                if kind == 'sshKey' and isinstance(v, dict):
+                    values.update(ssh_key(v))
+                elif v not in (None, '') and not isinstance(v, dict):
+                    values[f.get('title') or f.get('id')] = str(v)
+    return values or None
+
+
+def item_uris(overview):
+    urls = [u['url'] for u in overview.get('urls', []) if u.get('url')]
+    if not urls and overview.get('url'):
+        urls = [overview['url']]
+    return [Uri(u) for u in urls]
+
+
+def item_revision(item):
+    if not item.get('updatedAt'):
+        return None
+    return datetime.datetime.fromtimestamp(
+        item['updatedAt'], datetime.timezone.utc).isoformat()
+
+
+class OnePuxSource(Source):
+    def __init__(self, file):
+        try:
+            with zipfile.ZipFile(file) as z:
+                export = json.loads(z.read(EXPORT_DATA))
+        except (zipfile.BadZipFile, KeyError, ValueError) as e:
+            raise OnePasswordError(e)
+
+        self._folders = []
+        self._entries = []
+        try:
+            self.walk(export)
+        except (KeyError, TypeError, AttributeError) as e:
+            raise OnePasswordError(f"Unexpected {EXPORT_DATA} layout: {e!r}")
+
+    def walk(self, export):
+        for account in export.get('accounts', []):
+            for vault in account.get('vaults', []):
+                attrs = vault['attrs']
+                self._folders.append(Folder(attrs['uuid'], attrs['name']))
+                for item in vault.get('items', []):
+                    if item.get('state') == 'archived':
+                        continue
+                    self._entries.append(self.entry(item, attrs['uuid']))
+
+    def entry(self, item, vault_id):
+        details = item.get('details', {})
+        overview = item.get('overview', {})
+        password = login_field(details, 'password') or details.get('password')
+        return Entry(
+            name=overview.get('title'),
+            type=CATEGORIES.get(item.get('categoryUuid')),
+            folder_id=vault_id,
+            username=login_field(details, 'username'),
+            password=password,
+            uris=item_uris(overview),
+            totp=item_totp(details),
+            notes=details.get('notesPlain') or None,
+            details=item_details(details),
+            favorite=bool(item.get('favIndex')),
+            revision=item_revision(item),
+        )
+
+    def folders(self):
+        return self._folders
+
+    def entries(self):
+        return self._entries
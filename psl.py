@@ -0,0 +1,155 @@
+import ipaddress
+import os
+import re
+import urllib.parse
+
+import sources
+
+LIST = os.path.join(os.path.dirname(os.path.abspath(__file__)),
+                    'public_suffix_list.dat')
+
+# Schemes the browser extension fills in, anything else is kept as is
+WEB_SCHEMES = ('', 'http', 'https', 'ftp')
+
+URI = 'uri'
+DOMAIN = 'domain'
+HOST = 'host'
+
+# Optional groups like "(www\.)?" and literal dotted names in a regex
+OPTIONAL_GROUP = re.compile(r'\([^()]*\)[?*]')
+LITERAL_NAME = re.compile(r'[a-z0-9-]+(?:\.[a-z0-9-]+)*\.[a-z]{2,}\b',
+                          re.IGNORECASE)
+
+_rules = None
+
+
+class ServiceError(Exception):
+    pass
+
+
+def to_ascii(name):
+    labels = []
+    for label in name.split('.'):
+        try:
+            labels.append(label.encode('idna').decode('ascii'))
+        except UnicodeError:
+            labels.append(label)
+    return '.'.join(labels)
+
+
+def rules():
+    global _rules
+    if _rules is None:
+        _rules = set()
+        with open(LIST, 'r', encoding='utf-8') as f:
+            for line in f:
+                line = line.strip()
+                if not line or line.startswith('//'):
+                    continue
+                exception = line.startswith('!')
+                rule = to_ascii(line.lstrip('!'))
+                _rules.add(f"!{rule}" if exception else rule)
+    return _rules
+
+
+def public_suffix_len(labels):
+    # Number of trailing labels that form the public suffix
+    known = rules()
+    best = 1  # Unlisted TLDs count as a suffix of their own
+    for n in range(1, len(labels) + 1):
+        candidate = '.'.join(labels[-n:])
+        wildcard = '.'.join(['*'] + labels[-n + 1:]) if n > 1 else '*'
+        if f"!{candidate}" in known:
+            return n - 1
+        if candidate in known or wildcard in known:
+            best = n
+    return best
+
+
+def registrable_domain(host):
+    labels = host.split('.')
+    n = public_suffix_len(labels)
+    if n >= len(labels):
+        return host
+    return '.'.join(labels[-n - 1:])
+
+
+def is_ip(host):
+    try:
+        ipaddress.ip_address(host)
+    except ValueError:
+        return False
+    return True
+
+
+def split_uri(uri):
+    uri = uri.strip()
+    # "example.com/login" has no scheme and would parse as a path
+    if '://' not in uri:
+        uri = f"//{uri}"
+    url = urllib.parse.urlsplit(uri)
+    try:
+        port = url.port
+    except ValueError:
+        port = None
+    return url.scheme.lower(), url.hostname, port
+
+
+def with_port(host, port):
+    if not port:
+        return host
+    if ':' in host:
+        return f"[{host}]:{port}"
+    return f"{host}:{port}"
+
+
+def service_name(uri, mode=DOMAIN):
+    if mode == URI:
+        return uri
+
+    try:
+        scheme, host, port = split_uri(uri)
+    except ValueError:
+        return uri
+    if not host or scheme not in WEB_SCHEMES:
+        return uri
+
+    host = host.rstrip('.')
+    if is_ip(host):
+        # Services on one address are told apart by their port
+        return with_port(host, port)
+
+    host = to_ascii(host)
+    if '.' not in host:
+        # Intranet names like "nas:5000" have nothing else to go by either
+        return with_port(host, port)
+
+    if mode == HOST:
+        return host
+    return registrable_domain(host)
+
+
+def regex_domain(pattern):
+    # Best effort, only the host part of the pattern is looked at
+    host = pattern.replace('\\/', '/').replace('\\.', '.')
+    host = OPTIONAL_GROUP.sub('', host.partition('://')[2] or host)
+    host = host.split('/')[0]
+    domains = {registrable_domain(to_ascii(n.lower()))
+               for n in LITERAL_NAME.findall(host)}
+    if len(domains) != 1:
+        raise ServiceError(f"No single domain in regex {pattern}")
+    return domains.pop()
+
+
+def match_service(uri, mode=DOMAIN):
+    # An explicit match detection beats the mode, even URI
+    if uri.match == sources.MATCH_NEVER:
+        return None
+    if uri.match == sources.MATCH_REGEX:
+        return regex_domain(uri.uri)
+    if uri.match in (sources.MATCH_HOST, sources.MATCH_STARTS_WITH,
+                     sources.MATCH_EXACT):
+        return service_name(uri.uri, HOST)
+    if uri.match == sources.MATCH_DOMAIN:
+        return service_name(uri.uri, DOMAIN)
+    return service_name(uri.uri, mode)
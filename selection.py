@@ -0,0 +1,169 @@
+import argparse
+import re
+import urllib.parse
+from dataclasses import dataclass
+
+import sources
+
+KEYS = ('folder', 'collection', 'name', 'domain', 'username', 'type', 'org',
+        'favorite', 'revised')
+
+TYPES = (sources.LOGIN, sources.NOTE, sources.CARD, sources.IDENTITY,
+         sources.SSH_KEY)
+
+# Folder rules can name this to match items that aren't in any folder
+NO_FOLDER = 'No Folder'
+
+INCLUDE = 'include'
+EXCLUDE = 'exclude'
+ONLY = 'only'
+AUTO = 'auto'
+
+RULES_HELP = ('RULE is KEY:VALUE with KEY one of folder (exact name or '
+              f'"{NO_FOLDER}"), collection (exact name), name '
+              '(regex), domain, username, type (' + ', '.join(TYPES) + '), '
+              'org (organization id or "any"), favorite (yes/no) or revised '
+              '(FROM..TO dates, either may be left out), a bare VALUE is a '
+              'folder')
+
+
+class SelectionError(Exception):
+    pass
+
+
+@dataclass
+class Rule:
+    key: str
+    value: str
+
+
+def parse_date_range(value):
+    start, sep, end = value.partition('..')
+    if not sep:
+        raise ValueError
+    for date in (start, end):
+        if date and not re.fullmatch(r'\d{4}-\d{2}-\d{2}', date):
+            raise ValueError
+    return start, end
+
+
+def parse_rule(value):
+    key, sep, rest = value.partition(':')
+    rule = Rule(key, rest) if sep and key in KEYS else Rule('folder', value)
+
+    try:
+        if rule.key == 'name':
+            re.compile(rule.value)
+        elif rule.key == 'type' and rule.value not in TYPES:
+            raise ValueError
+        elif rule.key == 'favorite' and rule.value not in ('yes', 'no'):
+            raise ValueError
+        elif rule.key == 'revised':
+            parse_date_range(rule.value)
+    except (ValueError, re.error):
+        raise argparse.ArgumentTypeError(f"Invalid {rule.key} rule "
+                                         f"'{rule.value}'")
+    return rule
+
+
+def uri_host(uri):
+    # Bare "example.com" parses as a path, not a host
+    if '://' not in uri:
+        uri = f"//{uri}"
+    try:
+        return (urllib.parse.urlparse(uri).hostname or '').lower()
+    except ValueError:
+        return ''
+
+
+def group_allowed(member, mode):
+    if mode == EXCLUDE:
+        return not member
+    if mode == ONLY:
+        return member
+    return True
+
+
+class Selection:
+    def __init__(self, source, include, exclude, recursive=False,
+                 unfoldered=AUTO, trashed=EXCLUDE, organization=INCLUDE):
+        self.include = include
+        self.exclude = exclude
+        self.unfoldered = unfoldered
+        self.trashed = trashed
+        self.organization = organization
+
+        self.folders = {}
+        self.collections = {}
+        for rule in include + exclude:
+            if rule.key == 'folder':
+                ids = source.folder_ids(rule.value, recursive)
+                if rule.value == NO_FOLDER:
+                    ids.add(None)
+                if not ids:
+                    raise SelectionError(f"No folder named {rule.value}")
+                self.folders[rule.value] = ids
+            elif rule.key == 'collection':
+                ids = source.collection_ids(rule.value, recursive)
+                if not ids:
+                    raise SelectionError(f"No collection named {rule.value}")
+                self.collections[rule.value] = ids
+
+    def included_collections(self):
+        return [i for r in self.include if r.key == 'collection'
+                for i in self.collections[r.value]]
+
+    def matches(self, rule, e):
+        if rule.key == 'folder':
+            return e.folder_id in self.folders[rule.value]
+        if rule.key == 'collection':
+            return any(c in self.collections[rule.value]
+                       for c in e.collections)
+        if rule.key == 'name':
+            name = e.name or ''
+            return re.search(rule.value, name, re.IGNORECASE) is not None
+        if rule.key == 'domain':
+            domain = rule.value.lower()
+            hosts = [uri_host(u.uri) for u in e.uris]
+            return any(h == domain or h.endswith(f".{domain}") for h in hosts)
+        if rule.key == 'username':
+            return (e.username or '').lower() == rule.value.lower()
+        if rule.key == 'type':
+            return e.type == rule.value
+        if rule.key == 'org':
+            if rule.value == 'any':
+                return e.organization is not None
+            return e.organization == rule.value
+        if rule.key == 'favorite':
+            return e.favorite == (rule.value == 'yes')
+        if rule.key == 'revised':
+            if not e.revision:
+                return False
+            start, end = parse_date_range(rule.value)
+            date = e.revision[:10]
+            return (not start or date >= start) and (not end or date <= end)
+
+    def selected(self, e):
+        if not group_allowed(e.trashed, self.trashed):
+            return False
+        if not group_allowed(e.organization is not None, self.organization):
+            return False
+
+        unfoldered = not e.folder_id
+        if unfoldered and self.unfoldered == EXCLUDE:
+            return False
+        # With include, folder rules don't apply to items without a folder
+        skip_folders = unfoldered and self.unfoldered == INCLUDE
+
+        if any(self.matches(r, e) for r in self.exclude
+               if r.key != 'folder' or not skip_folders):
+            return False
+
+        # Rules on the same key are alternatives, different keys must all hold
+        for key in {r.key for r in self.include}:
+            if key == 'folder' and skip_folders:
+                continue
+            if not any(self.matches(r, e) for r in self.include
+                       if r.key == key):
+                return False
+        return True
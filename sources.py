@@ -0,0 +1,165 @@
+from dataclasses import dataclass, field
+
+LOGIN = 'login'
+NOTE = 'note'
+CARD = 'card'
+IDENTITY = 'identity'
+SSH_KEY = 'ssh_key'
+
+# Bitwarden URI match detection, None means the default (domain)
+MATCH_DOMAIN = 0
+MATCH_HOST = 1
+MATCH_STARTS_WITH = 2
+MATCH_EXACT = 3
+MATCH_REGEX = 4
+MATCH_NEVER = 5
+
+FIELD_TEXT = 'text'
+FIELD_HIDDEN = 'hidden'
+FIELD_BOOLEAN = 'boolean'
+FIELD_LINKED = 'linked'
+
+
+@dataclass
+class Folder:
+    id: str
+    name: str
+
+
+@dataclass
+class Uri:
+    uri: str
+    match: int = None
+    # Service name on the device, the raw URI until one is derived
+    service: str = None
+
+
+@dataclass
+class CustomField:
+    name: str
+    value: str
+    type: str = FIELD_TEXT
+    # 'username' or 'password' for linked fields
+    linked: str = None
+
+
+@dataclass
+class Attachment:
+    name: str
+    # None when the export only lists the attachment
+    data: bytes = None
+
+
+@dataclass
+class Entry:
+    name: str
+    type: str
+    id: str = None
+    folder_id: str = None
+    username: str = None
+    password: str = None
+    uris: list = field(default_factory=list)
+    totp: str = None
+    notes: str = None
+    details: dict = None
+    fields: list = field(default_factory=list)
+    description: str = None
+    attachments: list = field(default_factory=list)
+    favorite: bool = False
+    # ISO 8601 timestamp of the last change
+    revision: str = None
+    # Device category, 0 when uncategorized
+    category: int = 0
+    organization: str = None
+    trashed: bool = False
+    collections: list = field(default_factory=list)
+
+
+def matching_ids(groups, name, recursive):
+    # Nesting is only a naming convention: "Parent/Child"
+    return {g.id for g in groups if g.name == name or
+            (recursive and g.name.startswith(f"{name}/"))}
+
+
+class Source:
+    def folders(self):
+        raise NotImplementedError
+
+    def entries(self):
+        raise NotImplementedError
+
+    def collections(self):
+        return []
+
+    def folder_ids(self, name, recursive=False):
+        return matching_ids(self.folders(), name, recursive)
+
+    def collection_ids(self, name, recursive=False):
+        return matching_ids(self.collections(), name, recursive)
+
+
+BITWARDEN_FIELD_TYPES = {
+    0: FIELD_TEXT,
+    1: FIELD_HIDDEN,
+    2: FIELD_BOOLEAN,
+    3: FIELD_LINKED,
+}
+
+BITWARDEN_LINKED_IDS = {
+    100: 'username',
+    101: 'password',
+}
+
+BITWARDEN_TYPES = {
+    1: LOGIN,
+    2: NOTE,
+    3: CARD,
+    4: IDENTITY,
+    5: SSH_KEY,
+}
+
+
+class BitwardenSource(Source):
+    def __init__(self, js):
+        self.js = js
+
+    def folders(self):
+        return [Folder(f['id'], f['name']) for f in self.js['folders']]
+
+    def collections(self):
+        # Collections are named groups just like folders
+        return [Folder(c['id'], c['name'])
+                for c in self.js.get('collections') or []]
+
+    def entries(self):
+        for i in self.js['items']:
+            login = i.get('login') or {}
+            yield Entry(
+                name=i.get('name'),
+                type=BITWARDEN_TYPES.get(i.get('type')),
+                id=i.get('id'),
+                folder_id=i.get('folderId'),
+                username=login.get('username'),
+                password=login.get('password'),
+                uris=[Uri(u['uri'], u.get('match'))
+                      for u in login.get('uris') or []],
+                totp=login.get('totp'),
+                notes=i.get('notes'),
+                details=i.get('card') or i.get('identity') or i.get('sshKey'),
+                fields=[self.field(f) for f in i.get('fields') or []],
+                attachments=[Attachment(a.get('fileName'), a.get('data'))
+                             for a in i.get('attachments') or []],
+                favorite=bool(i.get('favorite')),
+                revision=i.get('revisionDate'),
+                organization=i.get('organizationId'),
+                trashed=bool(i.get('deletedDate')),
+                collections=i.get('collectionIds') or [],
+            )
+
+    def field(self, f):
+        return CustomField(
+            name=f.get('name'),
+            value=f.get('value'),
+            type=BITWARDEN_FIELD_TYPES.get(f.get('type'), FIELD_TEXT),
+            linked=BITWARDEN_LINKED_IDS.get(f.get('linkedId')),
+        )
@@ -0,0 +1,74 @@
+import base64
+import hashlib
+import json
+
+from cryptography.exceptions import UnsupportedAlgorithm
+from cryptography.hazmat.primitives import serialization
+
+# Data file moolticute-ssh-agent loads its keys from
+DATA_FILE = 'Moolticute SSH Keys'
+
+
+class SshKeyError(Exception):
+    pass
+
+
+def fingerprint(public_key):
+    try:
+        blob = base64.b64decode(public_key.split()[1], validate=True)
+    except (IndexError, ValueError):
+        raise SshKeyError("Malformed public key")
+    digest = base64.b64encode(hashlib.sha256(blob).digest()).decode()
+    return f"SHA256:{digest.rstrip('=')}"
+
+
+def load_private_key(data, passphrase):
+    data = data.encode('utf-8')
+    loader = serialization.load_pem_private_key
+    if b'BEGIN OPENSSH PRIVATE KEY' in data:
+        loader = serialization.load_ssh_private_key
+
+    try:
+        return loader(data, password=None)
+    except TypeError:
+        if passphrase is None:
+            raise SshKeyError("Key is encrypted, use --ssh-passphrase")
+
+    try:
+        return loader(data, password=passphrase.encode('utf-8'))
+    except (TypeError, ValueError):
+        raise SshKeyError("Wrong passphrase")
+
+
+def convert(details, passphrase=None):
+    if not details or not details.get('privateKey'):
+        raise SshKeyError("No private key")
+
+    try:
+        key = load_private_key(details['privateKey'], passphrase)
+    except (ValueError, UnsupportedAlgorithm) as e:
+        raise SshKeyError(f"Unsupported private key: {e}")
+
+    public_key = key.public_key().public_bytes(
+        serialization.Encoding.OpenSSH,
+        serialization.PublicFormat.OpenSSH).decode()
+    converted = fingerprint(public_key)
+
+    # Make sure nothing got lost, the stored key must be the one Bitwarden had
+    public = details.get('publicKey')
+    if public and fingerprint(public) != converted:
+        raise SshKeyError("Public key doesn't match the private key")
+    expected = details.get('keyFingerprint')
+    if expected and expected != converted:
+        raise SshKeyError(f"Fingerprint mismatch, expected {expected} got "
+                          f"{converted}")
+
+    pem = key.private_bytes(serialization.Encoding.PEM,
+                            serialization.PrivateFormat.OpenSSH,
+                            serialization.NoEncryption())
+    return pem, converted
+
+
+def keys_file(keys):
+    # The agent keeps a JSON list of raw keys, base64 like Go encodes []byte
+    return json.dumps([base64.b64encode(k).decode() for k in keys]).encode()
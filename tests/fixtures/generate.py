@@ -0,0 +1,404 @@
+#!/usr/bin/env python
+# Rebuilds the fixture vaults, run from the repository root:
+#   python tests/fixtures/generate.py
+import base64
+import hashlib
+import hmac
+import gzip
+import json
+import os
+import struct
+import zipfile
+
+from cryptography.hazmat.primitives import hashes, padding, serialization
+from cryptography.hazmat.primitives.asymmetric import padding as asym_padding
+from cryptography.hazmat.primitives.asymmetric import rsa
+from cryptography.hazmat.primitives.ciphers import Cipher, algorithms, modes
+from cryptography.hazmat.primitives.kdf.argon2 import Argon2d, Argon2id
+from cryptography.hazmat.primitives.kdf.hkdf import HKDFExpand
+
+FIXTURES = os.path.dirname(os.path.abspath(__file__))
+
+EXPORT_PASSWORD = 'export password'
+EMAIL = 'Alice@Example.com'
+MASTER_PASSWORD = 'master password'
+ACCOUNT_ITERATIONS = 5000
+
+VAULT = {
+    'encrypted': False,
+    'folders': [
+        {'id': 'f1', 'name': 'Work'},
+        {'id': 'f2', 'name': 'Work/Clients'},
+    ],
+    'items': [
+        {'id': 'i1', 'folderId': 'f1', 'type': 1, 'name': 'Mail',
+         'favorite': True, 'notes': 'Work mail',
+         'fields': [{'name': 'PIN', 'value': '1234', 'type': 1}],
+         'login': {'username': 'alice', 'password': 'mail pw',
+                   'totp': 'JBSWY3DPEHPK3PXP',
+                   'uris': [{'uri': 'https://mail.example.com/',
+                             'match': None}]}},
+        {'id': 'i2', 'folderId': 'f2', 'type': 1, 'name': 'Acme',
+         'favorite': False, 'notes': None,
+         'login': {'username': 'alice@acme.com', 'password': 'acme pw',
+                   'uris': [{'uri': 'https://portal.acme.co.uk/login',
+                             'match': 1}]}},
+        {'id': 'i3', 'folderId': None, 'type': 2, 'name': 'Note',
+         'favorite': False, 'notes': 'Secret note'},
+    ],
+}
+
+# Values Bitwarden encrypts, everything else is stored as is
+ENCRYPTED_KEYS = ('name', 'notes', 'username', 'password', 'totp', 'uri',
+                  'value')
+
+
+def stretch(key):
+    return (HKDFExpand(hashes.SHA256(), 32, b'enc').derive(key),
+            HKDFExpand(hashes.SHA256(), 32, b'mac').derive(key))
+
+
+def split(key):
+    return key[:32], key[32:]
+
+
+def encrypt(data, key):
+    if isinstance(data, str):
+        data = data.encode('utf-8')
+    enc, mac = key
+    iv = os.urandom(16)
+    padder = padding.PKCS7(128).padder()
+    padded = padder.update(data) + padder.finalize()
+    encryptor = Cipher(algorithms.AES(enc), modes.CBC(iv)).encryptor()
+    ct = encryptor.update(padded) + encryptor.finalize()
+    tag = hmac.new(mac, iv + ct, hashlib.sha256).digest()
+    return '2.' + '|'.join(base64.b64encode(p).decode()
+                           for p in (iv, ct, tag))
+
+
+def encrypt_values(value, key):
+    if isinstance(value, list):
+        return [encrypt_values(v, key) for v in value]
+    if isinstance(value, dict):
+        return {k: encrypt(v, key) if k in ENCRYPTED_KEYS and v is not None
+                else encrypt_values(v, key) for k, v in value.items()}
+    return value
+
+
+def write_json(name, js):
+    with open(os.path.join(FIXTURES, name), 'w') as f:
+        json.dump(js, f, indent=2)
+        f.write('\n')
+
+
+def password_protected(name, kdf, iterations, memory=None, parallelism=None):
+    salt = base64.b64encode(os.urandom(16)).decode()
+    if kdf == 0:
+        key = hashlib.pbkdf2_hmac('sha256', EXPORT_PASSWORD.encode(),
+                                  salt.encode(), iterations, 32)
+    else:
+        key = Argon2id(salt=hashlib.sha256(salt.encode()).digest(),
+                       length=32, iterations=iterations, lanes=parallelism,
+                       memory_cost=memory * 1024).derive(
+                           EXPORT_PASSWORD.encode())
+    key = stretch(key)
+    write_json(name, {
+        'encrypted': True,
+        'passwordProtected': True,
+        'salt': salt,
+        'kdfType': kdf,
+        'kdfIterations': iterations,
+        'kdfMemory': memory,
+        'kdfParallelism': parallelism,
+        'encKeyValidation_DO_NOT_EDIT': encrypt(os.urandom(16).hex(), key),
+        'data': encrypt(json.dumps(VAULT), key),
+    })
+
+
+def account_restricted(name):
+    master_key = hashlib.pbkdf2_hmac('sha256', MASTER_PASSWORD.encode(),
+                                     EMAIL.lower().encode(),
+                                     ACCOUNT_ITERATIONS, 32)
+    user_key = os.urandom(64)
+    user = split(user_key)
+
+    items = [encrypt_values(i, user) for i in VAULT['items']]
+    # The first item has its own key like newer clients write them
+    item_key = os.urandom(64)
+    items[0] = encrypt_values(VAULT['items'][0], split(item_key))
+    items[0]['key'] = encrypt(item_key, user)
+
+    write_json(name, {
+        'encrypted': True,
+        'encKeyValidation_DO_NOT_EDIT': encrypt(os.urandom(16).hex(), user),
+        'folders': encrypt_values(VAULT['folders'], user),
+        'items': items,
+    })
+    with open(os.path.join(FIXTURES, f"{name}.user_key"), 'w') as f:
+        f.write(encrypt(user_key, stretch(master_key)) + '\n')
+
+
+def server_sync(name):
+    master_key = hashlib.pbkdf2_hmac('sha256', MASTER_PASSWORD.encode(),
+                                     EMAIL.lower().encode(),
+                                     ACCOUNT_ITERATIONS, 32)
+    user_key = os.urandom(64)
+    user = split(user_key)
+    org_key = os.urandom(64)
+    org = split(org_key)
+
+    private_key = rsa.generate_private_key(65537, 2048)
+    der = private_key.private_bytes(serialization.Encoding.DER,
+                                    serialization.PrivateFormat.PKCS8,
+                                    serialization.NoEncryption())
+    oaep = asym_padding.OAEP(mgf=asym_padding.MGF1(hashes.SHA1()),
+                             algorithm=hashes.SHA1(), label=None)
+    wrapped = private_key.public_key().encrypt(org_key, oaep)
+
+    personal, other, _ = VAULT['items']
+    item_key = os.urandom(64)
+    ciphers = [encrypt_values(personal, split(item_key)),
+               encrypt_values(dict(other, organizationId='o1',
+                                   collectionIds=['c1']), org),
+               # Membership isn't confirmed yet, there is no key for it
+               encrypt_values(dict(other, id='i4', organizationId='o2'),
+                              split(os.urandom(64)))]
+    ciphers[0]['key'] = encrypt(item_key, user)
+    # Older layout of the same fields, ignored
+    ciphers[0]['data'] = {'name': ciphers[0]['name']}
+
+    write_json(name, {
+        'object': 'sync',
+        'profile': {
+            'email': EMAIL,
+            'key': encrypt(user_key, stretch(master_key)),
+            'privateKey': encrypt(der, user),
+            'organizations': [
+                {'id': 'o1', 'name': 'Team',
+                 'key': '4.' + base64.b64encode(wrapped).decode()},
+                {'id': 'o2', 'name': 'Invited', 'key': None},
+            ],
+        },
+        'folders': encrypt_values(VAULT['folders'], user),
+        'collections': [
+            {'id': 'c1', 'organizationId': 'o1',
+             'name': encrypt('Shared', org)},
+            {'id': 'c2', 'organizationId': 'o2',
+             'name': encrypt('Invited', split(os.urandom(64)))},
+        ],
+        'ciphers': ciphers,
+    })
+
+
+def bitwarden_zip(name):
+    vault = json.loads(json.dumps(VAULT))
+    # Listed with its metadata only, the file comes from the archive
+    vault['items'][0]['attachments'] = [
+        {'id': 'a1', 'fileName': 'scan.png', 'size': '4'}]
+    with zipfile.ZipFile(os.path.join(FIXTURES, name), 'w') as z:
+        z.writestr('data.json', json.dumps(vault, indent=2))
+        z.writestr('attachments/i1/scan.png', b'\x89PNG')
+        z.writestr('attachments/i2/contract.txt', b'Signed')
+        # Not below an item directory, ignored
+        z.writestr('attachments/stray.txt', b'Stray')
+
+
+KEEPASS_PASSWORD = 'keepass password'
+
+KDBX_SIGNATURE = b'\x03\xd9\xa2\x9a\x67\xfb\x4b\xb5'
+KDBX_AES256 = bytes.fromhex('31c1f2e6bf714350be5805216afc5aff')
+KDBX_CHACHA20 = bytes.fromhex('d6038a2b8b6f4cb5a524339a31dbb59a')
+KDBX_AES_KDF = bytes.fromhex('c9d9f39a628a4460bf740d08c18a4fea')
+KDBX_ARGON2D = bytes.fromhex('ef636ddf8c29444b91f7a9a403e30a0c')
+KDBX_ARGON2ID = bytes.fromhex('9e298b1956db4773b23dfc3ec6f0a1e6')
+
+KEEPASS_ENTRIES = '''
+<Entry><UUID>{uuid}</UUID>
+ <String><Key>Title</Key><Value>{title}</Value></String>
+ <String><Key>UserName</Key><Value>{username}</Value></String>
+ <String><Key>Password</Key><Value Protected="True">{password}</Value>
+ </String>
+ <String><Key>URL</Key><Value>{url}</Value></String>
+ <String><Key>Notes</Key><Value>{notes}</Value></String>
+ {extra}
+ <History><Entry><UUID>{uuid}</UUID>
+  <String><Key>Title</Key><Value>{title} (old)</Value></String>
+  <String><Key>Password</Key><Value Protected="True">{old}</Value></String>
+ </Entry></History>
+</Entry>'''
+
+KEEPASS_XML = '''<?xml version="1.0" encoding="utf-8" standalone="yes"?>
+<KeePassFile>
+ <Meta><Generator>generate.py</Generator>
+  <RecycleBinUUID>AAAAAAAAAAAAAAAAAAAAAw==</RecycleBinUUID></Meta>
+ <Root><Group><UUID>AAAAAAAAAAAAAAAAAAAAAA==</UUID><Name>Database</Name>
+  {top}
+  <Group><UUID>AAAAAAAAAAAAAAAAAAAAAQ==</UUID><Name>Work</Name>
+   {mail}
+   <Group><UUID>AAAAAAAAAAAAAAAAAAAAAg==</UUID><Name>Clients</Name>
+    {acme}
+   </Group>
+  </Group>
+  <Group><UUID>AAAAAAAAAAAAAAAAAAAAAw==</UUID><Name>Recycle Bin</Name>
+   {deleted}
+  </Group>
+ </Group></Root>
+</KeePassFile>'''
+
+
+def kdbx_field(field_id, data):
+    return bytes([field_id]) + struct.pack('<I', len(data)) + data
+
+
+def variant_dictionary(items):
+    data = struct.pack('<H', 0x100)
+    for kind, key, value in items:
+        data += (bytes([kind]) + struct.pack('<I', len(key)) + key.encode() +
+                 struct.pack('<I', len(value)) + value)
+    return data + b'\x00'
+
+
+def keepass_xml(protect):
+    # Protected values are encrypted in document order
+    def entry(uuid, title, username, password, url, notes='', extra=()):
+        current = protect(password)
+        strings = ''
+        for key, value, protected in extra:
+            attribute = ' Protected="True"' if protected else ''
+            value = protect(value) if protected else value
+            strings += (f'<String><Key>{key}</Key>'
+                        f'<Value{attribute}>{value}</Value></String>')
+        return KEEPASS_ENTRIES.format(
+            uuid=uuid, title=title, username=username, url=url, notes=notes,
+            extra=strings, password=current, old=protect(f"old {password}"))
+
+    top = entry('AAAAAAAAAAAAAAAAAAAAEA==', 'Router', 'admin', 'router pw',
+                'http://192.168.1.1')
+    mail = entry('AAAAAAAAAAAAAAAAAAAAEQ==', 'Mail', 'alice', 'mail pw',
+                 'https://mail.example.com', 'Work mail',
+                 [('otp', 'otpauth://totp/Mail?secret=JBSWY3DPEHPK3PXP',
+                   True),
+                  ('PIN', '1234', True),
+                  ('Account', '42', False)])
+    acme = entry('AAAAAAAAAAAAAAAAAAAAEg==', 'Acme', 'alice@acme.com',
+                 'acme pw', 'acme.co.uk')
+    deleted = entry('AAAAAAAAAAAAAAAAAAAAEw==', 'Old', 'old', 'deleted pw',
+                    '')
+    return KEEPASS_XML.format(top=top, mail=mail, acme=acme, deleted=deleted)
+
+
+def keepass_key(password, keyfile, params):
+    parts = b''
+    if password:
+        parts += hashlib.sha256(password.encode()).digest()
+    if keyfile:
+        parts += keyfile
+    key = hashlib.sha256(parts).digest()
+
+    if params[0] == KDBX_AES_KDF:
+        _, seed, rounds = params
+        encryptor = Cipher(algorithms.AES(seed), modes.ECB()).encryptor()
+        for _ in range(rounds):
+            key = encryptor.update(key)
+        return hashlib.sha256(key).digest()
+
+    kdf, salt, iterations, memory, lanes = params
+    argon = Argon2d if kdf == KDBX_ARGON2D else Argon2id
+    return argon(salt=salt, length=32, iterations=iterations, lanes=lanes,
+                 memory_cost=memory // 1024).derive(key)
+
+
+def keepass(name, cipher, kdf, password=None, keyfile=None, compress=True):
+    salt = os.urandom(32)
+    if kdf == KDBX_AES_KDF:
+        params = (kdf, salt, 1000)
+        dictionary = [(0x42, '$UUID', kdf), (0x42, 'S', salt),
+                      (0x05, 'R', struct.pack('<Q', 1000))]
+    else:
+        params = (kdf, salt, 2, 1024 * 1024, 2)
+        dictionary = [(0x42, '$UUID', kdf), (0x42, 'S', salt),
+                      (0x05, 'I', struct.pack('<Q', 2)),
+                      (0x05, 'M', struct.pack('<Q', 1024 * 1024)),
+                      (0x04, 'P', struct.pack('<I', 2)),
+                      (0x04, 'V', struct.pack('<I', 0x13))]
+
+    seed = os.urandom(32)
+    iv = os.urandom(16 if cipher == KDBX_AES256 else 12)
+    header = (KDBX_SIGNATURE + struct.pack('<HH', 1, 4) +
+              kdbx_field(2, cipher) +
+              kdbx_field(3, struct.pack('<I', int(compress))) +
+              kdbx_field(4, seed) + kdbx_field(7, iv) +
+              kdbx_field(11, variant_dictionary(dictionary)) +
+              kdbx_field(0, b'\r\n\r\n'))
+
+    transformed = keepass_key(password, keyfile, params)
+    hmac_key = hashlib.sha512(seed + transformed + b'\x01').digest()
+    header_key = hashlib.sha512(b'\xff' * 8 + hmac_key).digest()
+    data = (header + hashlib.sha256(header).digest() +
+            hmac.new(header_key, header, hashlib.sha256).digest())
+
+    stream_key = os.urandom(64)
+    digest = hashlib.sha512(stream_key).digest()
+    stream = Cipher(algorithms.ChaCha20(digest[:32],
+                                        b'\x00' * 4 + digest[32:44]),
+                    None).encryptor()
+    xml = keepass_xml(lambda v: base64.b64encode(
+        stream.update(v.encode())).decode())
+    inner = (kdbx_field(1, struct.pack('<I', 3)) + kdbx_field(2, stream_key) +
+             kdbx_field(0, b'') + xml.encode())
+    if compress:
+        inner = gzip.compress(inner)
+
+    key = hashlib.sha256(seed + transformed).digest()
+    if cipher == KDBX_AES256:
+        padder = padding.PKCS7(128).padder()
+        inner = padder.update(inner) + padder.finalize()
+        encryptor = Cipher(algorithms.AES(key), modes.CBC(iv)).encryptor()
+    else:
+        encryptor = Cipher(algorithms.ChaCha20(key, b'\x00' * 4 + iv),
+                           None).encryptor()
+    payload = encryptor.update(inner) + encryptor.finalize()
+
+    # Small blocks so the payload spans several of them
+    blocks = [payload[n:n + 1024] for n in range(0, len(payload), 1024)]
+    for index, block in enumerate(blocks + [b'']):
+        index = struct.pack('<Q', index)
+        size = struct.pack('<i', len(block))
+        block_key = hashlib.sha512(index + hmac_key).digest()
+        data += (hmac.new(block_key, index + size + block,
+                          hashlib.sha256).digest() + size + block)
+
+    with open(os.path.join(FIXTURES, name), 'wb') as f:
+        f.write(data)
+
+
+def keepass_keyfile(name):
+    # Version 2.0 XML keyfile as KeePassXC writes them
+    key = os.urandom(32)
+    hex_key = key.hex().upper()
+    groups = ' '.join(hex_key[n:n + 8] for n in range(0, 64, 8))
+    with open(os.path.join(FIXTURES, name), 'w') as f:
+        f.write('<?xml version="1.0" encoding="utf-8"?>\n<KeyFile>\n'
+                '  <Meta><Version>2.0</Version></Meta>\n'
+                f'  <Key><Data Hash="{hashlib.sha256(key).hexdigest()[:8]}">'
+                f'{groups}</Data></Key>\n</KeyFile>\n')
+    return key
+
+
+if __name__ == '__main__':
+    password_protected('password_protected_pbkdf2.json', 0, 1000)
+    password_protected('password_protected_argon2id.json', 1, 3, 16, 1)
+    account_restricted('account_restricted.json')
+    server_sync('server_sync.json')
+    bitwarden_zip('bitwarden.zip')
+    keepass('keepass_password.kdbx', KDBX_AES256, KDBX_ARGON2D,
+            KEEPASS_PASSWORD)
+    keepass('keepass_keyfile.kdbx', KDBX_CHACHA20, KDBX_ARGON2ID,
+            KEEPASS_PASSWORD, keepass_keyfile('keepass.keyx'),
+            compress=False)
+    # Any 32 byte file is used as the key as is
+    binary_key = os.urandom(32)
+    with open(os.path.join(FIXTURES, 'keepass.key'), 'wb') as f:
+        f.write(binary_key)
+    keepass('keepass_keyfile_only.kdbx', KDBX_AES256, KDBX_AES_KDF,
+            keyfile=binary_key)
@@ -0,0 +1,50 @@
+import os
+import unittest
+
+import browsers
+from tests.fixtures import generate
+
+FIXTURES = os.path.dirname(generate.__file__)
+
+
+def fixture(name):
+    with open(os.path.join(FIXTURES, name), newline='') as f:
+        return f.read()
+
+
+class DetectTest(unittest.TestCase):
+    def test_detect(self):
+        self.assertEqual(browsers.detect(fixture('chrome.csv')),
+                         browsers.CHROME)
+        self.assertEqual(browsers.detect(fixture('firefox.csv')),
+                         browsers.FIREFOX)
+        self.assertIsNone(browsers.detect(fixture('bitwarden.csv')))
+        self.assertIsNone(browsers.detect(''))
+
+
+class BrowserSourceTest(unittest.TestCase):
+    def entries(self, name):
+        source = browsers.BrowserSource(fixture(name))
+        return [(e.name, e.username, e.password, [u.uri for u in e.uris],
+                 e.notes) for e in source.entries()]
+
+    def test_chrome(self):
+        # Without a name the host stands in for it
+        self.assertEqual(self.entries('chrome.csv'), [
+            ('Example', 'alice', 'example pw',
+             ['https://www.example.com/login'], 'Work account'),
+            ('shop.example.org', 'bob', 'shop pw',
+             ['https://shop.example.org/'], ''),
+        ])
+
+    def test_firefox(self):
+        self.assertEqual(self.entries('firefox.csv'), [
+            ('www.example.com', 'alice', 'example pw',
+             ['https://www.example.com'], None),
+            ('intranet', 'carol', 'intranet pw', ['https://intranet:8443'],
+             None),
+        ])
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,72 @@
+import json
+import os
+import sys
+import unittest
+from unittest import mock
+
+import bwcrypto
+import convert
+from tests.fixtures import generate
+
+FIXTURES = os.path.dirname(generate.__file__)
+
+
+def fixture(name):
+    with open(os.path.join(FIXTURES, name)) as f:
+        return f.read()
+
+
+class PasswordProtectedTest(unittest.TestCase):
+    def check(self, name):
+        js = json.loads(fixture(name))
+        plain = bwcrypto.decrypt_password_protected(js,
+                                                    generate.EXPORT_PASSWORD)
+        self.assertEqual(plain, generate.VAULT)
+
+        with self.assertRaises(bwcrypto.DecryptionError):
+            bwcrypto.decrypt_password_protected(js, 'wrong password')
+
+    def test_pbkdf2(self):
+        self.check('password_protected_pbkdf2.json')
+
+    def test_argon2id(self):
+        self.check('password_protected_argon2id.json')
+
+
+class AccountRestrictedTest(unittest.TestCase):
+    def setUp(self):
+        self.js = json.loads(fixture('account_restricted.json'))
+        self.protected_key = fixture('account_restricted.json.user_key')
+
+    def user_key(self, password):
+        return bwcrypto.make_user_key(generate.EMAIL, password,
+                                      self.protected_key.strip(),
+                                      bwcrypto.KDF_PBKDF2,
+                                      generate.ACCOUNT_ITERATIONS)
+
+    def test_decrypt(self):
+        plain = bwcrypto.decrypt_account_restricted(
+            self.js, self.user_key(generate.MASTER_PASSWORD))
+        self.assertEqual(plain['folders'], generate.VAULT['folders'])
+        # Includes the item wrapped in its own key
+        self.assertEqual(plain['items'], generate.VAULT['items'])
+
+    def test_wrong_password(self):
+        with self.assertRaises(bwcrypto.DecryptionError):
+            self.user_key('wrong password')
+
+
+class KdfIterationsTest(unittest.TestCase):
+    def iterations(self, *argv):
+        with mock.patch.object(sys, 'argv', ['convert.py', *argv]):
+            return convert.get_args().kdf_iterations
+
+    def test_defaults(self):
+        self.assertEqual(self.iterations('-f', 'x.json'), 600000)
+        self.assertEqual(self.iterations('--kdf', 'argon2id'), 3)
+        self.assertEqual(self.iterations('--kdf', 'argon2id',
+                                         '--kdf-iterations', '5'), 5)
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,99 @@
+import copy
+import http.server
+import json
+import threading
+import unittest
+import urllib.parse
+
+import bwserve
+from tests.fixtures import generate
+
+TRASHED = {'id': 'i4', 'folderId': None, 'type': 1, 'name': 'Old',
+           'deletedDate': '2024-01-01T00:00:00.000Z',
+           'login': {'username': 'old', 'password': 'old pw', 'uris': []}}
+
+
+class StandIn(http.server.BaseHTTPRequestHandler):
+    status = 'unlocked'
+    # Raw body for a path, to serve malformed answers
+    raw = {}
+
+    def do_GET(self):
+        url = urllib.parse.urlparse(self.path)
+        if self.path in self.raw:
+            body = self.raw[self.path]
+        else:
+            body = json.dumps(self.reply(url.path, url.query))
+        self.send_response(200)
+        self.send_header('Content-Type', 'application/json')
+        self.end_headers()
+        self.wfile.write(body.encode())
+
+    def reply(self, path, query):
+        if path == '/status':
+            data = {'object': 'template',
+                    'template': {'status': self.status}}
+        elif path == '/list/object/folders':
+            folders = generate.VAULT['folders'] + [{'id': None,
+                                                    'name': 'No Folder'}]
+            data = {'object': 'list', 'data': folders}
+        elif path == '/list/object/items':
+            items = [TRASHED] if query == 'trash' else generate.VAULT['items']
+            data = {'object': 'list', 'data': items}
+        else:
+            return {'success': False, 'message': 'Not found'}
+        return {'success': True, 'data': copy.deepcopy(data)}
+
+    def log_message(self, *args):
+        pass
+
+
+class ServeTest(unittest.TestCase):
+    def setUp(self):
+        StandIn.status = 'unlocked'
+        StandIn.raw = {}
+        self.server = http.server.HTTPServer(('127.0.0.1', 0), StandIn)
+        threading.Thread(target=self.server.serve_forever,
+                         daemon=True).start()
+        self.url = f"http://127.0.0.1:{self.server.server_address[1]}"
+
+    def tearDown(self):
+        self.server.shutdown()
+        self.server.server_close()
+
+    def test_load(self):
+        js = bwserve.load_serve(self.url)
+        self.assertEqual(js['folders'], generate.VAULT['folders'])
+        self.assertEqual(js['items'], generate.VAULT['items'])
+
+    def test_trash(self):
+        js = bwserve.load_serve(self.url, trash=True)
+        self.assertEqual(js['items'], generate.VAULT['items'] + [TRASHED])
+
+    def test_locked(self):
+        StandIn.status = 'locked'
+        with self.assertRaisesRegex(bwserve.ServeError, 'bw unlock'):
+            bwserve.load_serve(self.url)
+
+    def test_malformed(self):
+        for body in ('[]', 'not json', '{"success": true, "data": 1}',
+                     '{"success": true, "data": {}}',
+                     '{"success": true, "data": {"template": []}}'):
+            StandIn.raw = {'/status': body}
+            with self.assertRaises(bwserve.ServeError):
+                bwserve.load_serve(self.url)
+
+        for body in ('{"success": true, "data": {}}',
+                     '{"success": true, "data": {"data": {}}}',
+                     '{"success": true, "data": {"data": [1]}}'):
+            StandIn.raw = {'/list/object/items': body}
+            with self.assertRaises(bwserve.ServeError):
+                bwserve.load_serve(self.url)
+
+    def test_remote(self):
+        with self.assertRaisesRegex(bwserve.ServeError, 'not a local'):
+            bwserve.load_serve('http://example.com:8087')
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,104 @@
+import http.server
+import json
+import os
+import threading
+import unittest
+import urllib.parse
+from unittest import mock
+
+import bwcrypto
+import bwserver
+from tests.fixtures import generate
+
+TWO_FACTOR_CODE = '123456'
+
+
+def fixture(name):
+    with open(os.path.join(os.path.dirname(generate.__file__), name)) as f:
+        return json.load(f)
+
+
+class MockServer(http.server.BaseHTTPRequestHandler):
+    sync = None
+    token = 'access token'
+
+    def reply(self, js, status=200):
+        self.send_response(status)
+        self.send_header('Content-Type', 'application/json')
+        self.end_headers()
+        self.wfile.write(json.dumps(js).encode())
+
+    def do_POST(self):
+        body = self.rfile.read(int(self.headers['Content-Length'])).decode()
+        if self.path == '/identity/accounts/prelogin':
+            # Vaultwarden answers in PascalCase
+            return self.reply({'Kdf': 0,
+                               'KdfIterations': generate.ACCOUNT_ITERATIONS})
+        if self.path != '/identity/connect/token':
+            return self.reply({'message': 'Not found'}, 404)
+
+        form = {k: v[0] for k, v in urllib.parse.parse_qs(body).items()}
+        master_key = bwcrypto.make_master_key(
+            generate.EMAIL, generate.MASTER_PASSWORD, bwcrypto.KDF_PBKDF2,
+            generate.ACCOUNT_ITERATIONS)
+        expected = bwcrypto.master_password_hash(master_key,
+                                                 generate.MASTER_PASSWORD)
+        if form['password'] != expected:
+            return self.reply({'error': 'invalid_grant',
+                               'error_description': 'Username or password '
+                                                    'is incorrect'}, 400)
+        if form.get('twoFactorToken') != TWO_FACTOR_CODE:
+            return self.reply({'error': 'invalid_grant',
+                               'TwoFactorProviders': ['0']}, 400)
+        self.reply({'access_token': self.token, 'token_type': 'Bearer'})
+
+    def do_GET(self):
+        if self.headers['Authorization'] != f"Bearer {self.token}":
+            return self.reply({'message': 'Unauthorized'}, 401)
+        if not self.path.startswith('/api/sync'):
+            return self.reply({'message': 'Not found'}, 404)
+        self.reply(self.sync)
+
+    def log_message(self, *args):
+        pass
+
+
+class ServerTest(unittest.TestCase):
+    def setUp(self):
+        MockServer.sync = fixture('server_sync.json')
+        self.server = http.server.HTTPServer(('127.0.0.1', 0), MockServer)
+        threading.Thread(target=self.server.serve_forever,
+                         daemon=True).start()
+        self.url = f"http://127.0.0.1:{self.server.server_address[1]}/"
+
+    def tearDown(self):
+        self.server.shutdown()
+        self.server.server_close()
+
+    def load(self, password=generate.MASTER_PASSWORD):
+        with mock.patch('getpass.getpass', return_value=TWO_FACTOR_CODE):
+            return bwserver.load_server(self.url, generate.EMAIL, password)
+
+    def test_sync(self):
+        js, skipped = self.load()
+        personal, other, _ = generate.VAULT['items']
+
+        self.assertEqual(js['folders'], generate.VAULT['folders'])
+        self.assertEqual(js['collections'],
+                         [{'id': 'c1', 'organizationId': 'o1',
+                           'name': 'Shared'}])
+        # Personal item with its own key, then one unwrapped with the
+        # organization's RSA protected key
+        self.assertEqual(js['items'], [
+            personal,
+            dict(other, organizationId='o1', collectionIds=['c1']),
+        ])
+        self.assertEqual([c['id'] for c in skipped], ['i4'])
+
+    def test_wrong_password(self):
+        with self.assertRaisesRegex(bwserver.ServerError, 'incorrect'):
+            self.load('wrong password')
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,98 @@
+import contextlib
+import csv
+import io
+import os
+import sys
+import tempfile
+import unittest
+from unittest import mock
+
+import convert
+from tests.fixtures import generate
+
+FIXTURES = os.path.dirname(generate.__file__)
+
+
+def fixture(name, mode='r'):
+    with open(os.path.join(FIXTURES, name), mode) as f:
+        return f.read()
+
+
+class CsvTest(unittest.TestCase):
+    def setUp(self):
+        self.js = convert.load_csv(fixture('bitwarden.csv'))
+        self.items = {i['name']: i for i in self.js['items']}
+
+    def test_folders(self):
+        folders = {f['name']: f['id'] for f in self.js['folders']}
+        self.assertEqual(sorted(folders), ['Private', 'Work'])
+        # Items in the same folder share its made up id
+        self.assertEqual(self.items['Mail']['folderId'], folders['Work'])
+        self.assertEqual(self.items['Wiki']['folderId'], folders['Work'])
+        self.assertEqual(self.items['Recipes']['folderId'],
+                         folders['Private'])
+        self.assertIsNone(self.items['Bank']['folderId'])
+
+    def test_items(self):
+        mail = self.items['Mail']
+        self.assertEqual([u['uri'] for u in mail['login']['uris']],
+                         ['https://mail.example.com',
+                          'https://webmail.example.com'])
+        self.assertEqual((mail['login']['username'],
+                          mail['login']['password'], mail['login']['totp']),
+                         ('alice', 'mail pw', 'JBSWY3DPEHPK3PXP'))
+        self.assertTrue(mail['favorite'])
+        self.assertEqual([(f['name'], f['value']) for f in mail['fields']],
+                         [('PIN', '1234'), ('Q', 'blue')])
+
+        self.assertFalse(self.items['Wiki']['favorite'])
+        self.assertIsNone(self.items['Wiki']['login']['totp'])
+        self.assertEqual(self.items['Bank']['reprompt'], 1)
+        self.assertEqual(self.items['Recipes']['type'], 2)
+        self.assertNotIn('login', self.items['Recipes'])
+
+
+class ZipTest(unittest.TestCase):
+    def test_attachments(self):
+        js = convert.load_zip(fixture('bitwarden.zip', 'rb'), 'bitwarden.zip',
+                              None)
+        items = {i['id']: i for i in js['items']}
+
+        # Metadata from data.json is kept, the data added to it
+        self.assertEqual(items['i1']['attachments'], [
+            {'id': 'a1', 'fileName': 'scan.png', 'size': 4,
+             'data': b'\x89PNG'}])
+        self.assertEqual(items['i2']['attachments'], [
+            {'fileName': 'contract.txt', 'size': 6, 'data': b'Signed'}])
+        self.assertNotIn('attachments', items['i3'])
+
+
+class SplitByCollectionTest(unittest.TestCase):
+    def test_split(self):
+        vault = os.path.join(FIXTURES, 'collections.json')
+        out = io.StringIO()
+        with tempfile.TemporaryDirectory() as d:
+            output = os.path.join(d, 'out.csv')
+            argv = ['convert.py', '-f', vault, '-o', output,
+                    '--split-by-collection']
+            with mock.patch.object(sys, 'argv', argv):
+                with contextlib.redirect_stdout(out):
+                    convert.main()
+
+            written = {}
+            for name in sorted(os.listdir(d)):
+                with open(os.path.join(d, name), newline='') as f:
+                    written[name] = [r[0] for r in csv.reader(f)]
+
+        # Collection names are made safe for file names
+        self.assertEqual(written, {
+            'out.csv': ['home.com'],
+            'out.Eng_Infra.csv': ['cloud.com'],
+            'out.Ops.csv': ['pager.com'],
+        })
+        self.assertIn("Cloud is in Eng/Infra, Ops, written to Eng/Infra",
+                      out.getvalue())
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,83 @@
+import os
+import unittest
+
+import keepass
+from tests.fixtures import generate
+
+FIXTURES = os.path.dirname(generate.__file__)
+
+
+def fixture(name):
+    with open(os.path.join(FIXTURES, name), 'rb') as f:
+        return f.read()
+
+
+class KeePassTest(unittest.TestCase):
+    def open(self, name, password=generate.KEEPASS_PASSWORD, keyfile=None):
+        if keyfile:
+            keyfile = os.path.join(FIXTURES, keyfile)
+        root = keepass.open_database(fixture(name), password, keyfile)
+        return keepass.KeePassSource(root)
+
+    def check(self, source):
+        folders = {f.id: f.name for f in source.folders()}
+        self.assertEqual(sorted(folders.values()),
+                         ['Recycle Bin', 'Work', 'Work/Clients'])
+
+        entries = {e.name: e for e in source.entries()}
+        # History entries are left out
+        self.assertEqual(sorted(entries), ['Acme', 'Mail', 'Old', 'Router'])
+
+        mail = entries['Mail']
+        self.assertEqual(folders[mail.folder_id], 'Work')
+        self.assertEqual((mail.username, mail.password, mail.notes),
+                         ('alice', 'mail pw', 'Work mail'))
+        self.assertEqual([u.uri for u in mail.uris],
+                         ['https://mail.example.com'])
+        self.assertEqual(mail.totp,
+                         'otpauth://totp/Mail?secret=JBSWY3DPEHPK3PXP')
+        self.assertEqual([(f.name, f.value, f.type) for f in mail.fields],
+                         [('PIN', '1234', 'hidden'),
+                          ('Account', '42', 'text')])
+
+        self.assertEqual(folders[entries['Acme'].folder_id], 'Work/Clients')
+        self.assertEqual(entries['Acme'].password, 'acme pw')
+        self.assertIsNone(entries['Router'].folder_id)
+        self.assertEqual(entries['Old'].password, 'deleted pw')
+        self.assertTrue(entries['Old'].trashed)
+        self.assertFalse(any(entries[n].trashed
+                             for n in ('Acme', 'Mail', 'Router')))
+
+    def test_password(self):
+        # AES and Argon2d, compressed
+        self.check(self.open('keepass_password.kdbx'))
+
+    def test_keyfile(self):
+        # ChaCha20 and Argon2id, uncompressed, XML keyfile
+        self.check(self.open('keepass_keyfile.kdbx', keyfile='keepass.keyx'))
+
+    def test_keyfile_only(self):
+        # AES-KDF, binary keyfile
+        self.check(self.open('keepass_keyfile_only.kdbx', password=None,
+                             keyfile='keepass.key'))
+
+    def test_wrong_key(self):
+        with self.assertRaisesRegex(keepass.KeePassError, 'Wrong password'):
+            self.open('keepass_password.kdbx', password='wrong password')
+        with self.assertRaisesRegex(keepass.KeePassError, 'Wrong password'):
+            self.open('keepass_keyfile.kdbx')
+        with self.assertRaisesRegex(keepass.KeePassError, 'keyfile'):
+            self.open('keepass_keyfile.kdbx', keyfile='missing.key')
+
+    def test_damaged(self):
+        data = fixture('keepass_password.kdbx')
+        damaged = [data[:n] for n in range(0, len(data), 13)]
+        damaged += [data[:n] + bytes([data[n] ^ 1]) + data[n + 1:]
+                    for n in range(8, len(data), 29)]
+        for d in damaged:
+            with self.assertRaises(keepass.KeePassError):
+                keepass.open_database(d, generate.KEEPASS_PASSWORD)
+
+
+if __name__ == '__main__':
+    unittest.main()
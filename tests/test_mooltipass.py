@@ -0,0 +1,190 @@
+import csv
+import io
+import os
+import tempfile
+import unittest
+from contextlib import redirect_stdout
+
+import fields
+import mooltipass
+from sources import CARD, IDENTITY, LOGIN, NOTE, CustomField, Entry, Uri
+
+
+def written(entries):
+    with tempfile.TemporaryDirectory() as d:
+        output = os.path.join(d, 'out.csv')
+        with redirect_stdout(io.StringIO()):
+            mooltipass.write_credentials(entries, output)
+        with open(output, newline='') as f:
+            return list(csv.reader(f))
+
+
+class NotesTest(unittest.TestCase):
+    def test_split_note(self):
+        limit = mooltipass.NOTE_MAX_LEN
+        self.assertEqual(mooltipass.split_note('short'), ['short'])
+        self.assertEqual(mooltipass.split_note(''), [''])
+
+        # Cut after the last line break that fits
+        lines = 'a' * 300 + '\n' + 'b' * 300 + '\n' + 'c' * 10
+        self.assertEqual(mooltipass.split_note(lines),
+                         ['a' * 300 + '\n', 'b' * 300 + '\n' + 'c' * 10])
+
+        # Hard cut without any line break
+        text = 'x' * (limit * 2 + 1)
+        parts = mooltipass.split_note(text)
+        self.assertEqual([len(p) for p in parts], [limit, limit, 1])
+        self.assertEqual(''.join(parts), text)
+
+    def test_notes(self):
+        short = Entry('Short', NOTE, notes='hello')
+        long = Entry('L' * 80, NOTE, notes='x' * (mooltipass.NOTE_MAX_LEN + 1))
+        untitled = Entry(None, NOTE, notes='no name')
+
+        records, failed = mooltipass.notes([short, long, untitled], False)
+        self.assertEqual(records, [
+            {'title': 'Short', 'content': 'hello'},
+            {'title': mooltipass.UNTITLED, 'content': 'no name'},
+        ])
+        self.assertEqual([(e, reason) for e, reason in failed], [
+            (long, f"{mooltipass.NOTE_MAX_LEN + 1} characters, the device "
+                   f"holds {mooltipass.NOTE_MAX_LEN}")])
+
+        records, failed = mooltipass.notes([long], True)
+        self.assertEqual(failed, [])
+        titles = [r['title'] for r in records]
+        self.assertEqual(titles, ['L' * 58 + ' (1/2)', 'L' * 58 + ' (2/2)'])
+        self.assertTrue(all(len(t) <= mooltipass.NOTE_TITLE_MAX_LEN
+                            for t in titles))
+
+
+class WriteCredentialsTest(unittest.TestCase):
+    def test_description(self):
+        bank = Entry('Bank', LOGIN, username='alice', password='p,w"1',
+                     uris=[Uri('https://bank.com', service='bank.com')],
+                     fields=[CustomField('PIN', '1234'),
+                             CustomField('Q', 'red, blue')])
+        mail = Entry('Mail', LOGIN, username='bob', password='pw',
+                     uris=[Uri('https://mail.com', service='mail.com')])
+        rules = [fields.Rule('*', fields.DESCRIPTION)]
+        fields.apply_rules([bank, mail], rules)
+
+        rows = written([bank, mail])
+        self.assertEqual([len(r) for r in rows], [4, 4])
+        self.assertEqual(rows, [
+            ['bank.com', 'alice', 'p,w"1', 'PIN: 1234; Q: red, blue'],
+            ['mail.com', 'bob', 'pw', ''],
+        ])
+
+    def test_no_description(self):
+        mail = Entry('Mail', LOGIN, username='bob', password='pw',
+                     uris=[Uri('https://mail.com', service='mail.com')])
+        self.assertEqual(written([mail]), [['mail.com', 'bob', 'pw']])
+
+
+def login(name, service=None, **kwargs):
+    uris = [Uri(f"https://{service}", service=service)] if service else []
+    return Entry(name, LOGIN, username=name.lower(), uris=uris, **kwargs)
+
+
+class FavoritesTest(unittest.TestCase):
+    def setUp(self):
+        self.old = login('Old', 'old.com', favorite=True,
+                         revision='2023-01-01T00:00:00Z')
+        self.new = login('New', 'new.com', favorite=True,
+                         revision='2024-06-01T00:00:00Z')
+        self.undated = login('Alpha', 'alpha.com', favorite=True)
+        self.no_uri = login('Bare', favorite=True,
+                            revision='2025-01-01T00:00:00Z')
+        self.plain = login('Plain', 'plain.com')
+        self.entries = [self.old, self.new, self.undated, self.no_uri,
+                        self.plain]
+
+    def ranked(self, slots, order, names=()):
+        records, skipped, missing = mooltipass.favorites(
+            self.entries, slots, order, list(names))
+        return ([(r['slot'], r['service']) for r in records],
+                [(e.name, reason) for e, reason in skipped], missing)
+
+    def test_revision(self):
+        # Most recent first, undated last, items without a URI skipped
+        self.assertEqual(self.ranked(10, 'revision'), (
+            [(0, 'new.com'), (1, 'old.com'), (2, 'alpha.com')],
+            [('Bare', "No URI to use as service")], []))
+
+    def test_name(self):
+        records, _, _ = self.ranked(10, 'name')
+        self.assertEqual(records,
+                         [(0, 'alpha.com'), (1, 'new.com'), (2, 'old.com')])
+
+    def test_overflow(self):
+        records, skipped, _ = self.ranked(2, 'revision')
+        self.assertEqual(records, [(0, 'new.com'), (1, 'old.com')])
+        self.assertEqual(skipped, [('Bare', "No URI to use as service"),
+                                   ('Alpha', "All 2 slots are taken")])
+
+    def test_named(self):
+        # Listed items go first, even when they aren't favorites
+        records, skipped, missing = self.ranked(
+            2, 'revision', ['Plain', 'Old', 'Nowhere'])
+        self.assertEqual(records, [(0, 'plain.com'), (1, 'old.com')])
+        self.assertEqual([name for name, _ in skipped],
+                         ['Bare', 'New', 'Alpha'])
+        self.assertEqual(missing, ['Nowhere'])
+
+    def test_field_credentials(self):
+        self.old.fields = [CustomField('PIN', '1234')]
+        extra, _, _ = fields.apply_rules(
+            self.entries, [fields.Rule('PIN', fields.CREDENTIAL)])
+        self.entries += extra
+        records, _, _ = self.ranked(10, 'revision')
+        self.assertEqual(len(records), 3)
+
+
+class SplitByCollectionTest(unittest.TestCase):
+    def test_split(self):
+        names = {'c1': 'Ops', 'c2': 'Eng', 'c3': 'Infra'}
+        personal = login('Personal', 'home.com')
+        ops = login('Ops', 'ops.com', collections=['c1'])
+        shared = login('Shared', 'shared.com', collections=['c1', 'c2'])
+        unknown = login('Unknown', 'x.com', collections=['c9'])
+        entries = [personal, ops, shared, unknown]
+
+        # Shared items go to the first collection by name
+        groups, split = mooltipass.split_by_collection(entries, names, [])
+        self.assertEqual(groups, {None: [personal, unknown], 'Ops': [ops],
+                                  'Eng': [shared]})
+        self.assertEqual(split, [(shared, ['Eng', 'Ops'])])
+
+        # unless the selection asked for one of them
+        groups, split = mooltipass.split_by_collection(entries, names,
+                                                       ['c1'])
+        self.assertEqual(groups, {None: [personal, unknown],
+                                  'Ops': [ops, shared]})
+        self.assertEqual(split, [(shared, ['Ops', 'Eng'])])
+
+
+class DataFilesTest(unittest.TestCase):
+    def test_data_files(self):
+        card = Entry('Visa', CARD, details={'number': '4111', 'code': ''})
+        untitled = Entry(None, IDENTITY, details={'firstName': 'Alice'},
+                         notes='x' * 100)
+
+        files, failed = mooltipass.data_files([card, untitled], 1000, set())
+        self.assertEqual([(name, data, stored)
+                          for name, data, _, stored in files], [
+            ('Visa', b'Name: Visa\nNumber: 4111\n', ['Number']),
+            (mooltipass.UNTITLED,
+             b'Name: Untitled\nFirst name: Alice\nNotes:\n' + b'x' * 100 +
+             b'\n', ['First name', 'Notes']),
+        ])
+        self.assertEqual(failed, [])
+
+        files, failed = mooltipass.data_files([card, untitled], 50, set())
+        self.assertEqual([f[2] for f in files], [card])
+        self.assertEqual(failed, [(untitled,
+                                   "141 bytes, the device takes 50")])
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,59 @@
+import io
+import json
+import unittest
+import zipfile
+
+from cryptography.hazmat.primitives import serialization
+from cryptography.hazmat.primitives.asymmetric import ed25519
+
+import onepassword
+import sources
+import sshkeys
+
+
+def archive(export):
+    data = io.BytesIO()
+    with zipfile.ZipFile(data, 'w') as z:
+        z.writestr(onepassword.EXPORT_DATA, json.dumps(export))
+    data.seek(0)
+    return data
+
+
+def export(items, attrs=None):
+    attrs = attrs or {'uuid': 'v1', 'name': 'Personal'}
+    return {'accounts': [{'vaults': [{'attrs': attrs, 'items': items}]}]}
+
+
+class OnePuxTest(unittest.TestCase):
+    def test_ssh_key(self):
+        key = ed25519.Ed25519PrivateKey.generate()
+        private = key.private_bytes(serialization.Encoding.PEM,
+                                    serialization.PrivateFormat.PKCS8,
+                                    serialization.NoEncryption()).decode()
+        public = key.public_key().public_bytes(
+            serialization.Encoding.OpenSSH,
+            serialization.PublicFormat.OpenSSH).decode()
+        item = {'categoryUuid': '114', 'overview': {'title': 'Server'},
+                'details': {'sections': [{'fields': [{
+                    'title': 'private key', 'id': 'private_key',
+                    'value': {'sshKey': {
+                        'privateKey': private,
+                        'metadata': {'publicKey': public,
+                                     'fingerprint':
+                                         sshkeys.fingerprint(public)}}}}]}]}}
+
+        entry, = onepassword.OnePuxSource(archive(export([item]))).entries()
+        self.assertEqual(entry.type, sources.SSH_KEY)
+        _, fingerprint = sshkeys.convert(entry.details)
+        self.assertEqual(fingerprint, sshkeys.fingerprint(public))
+
+    def test_malformed(self):
+        for js in ({'accounts': [{'vaults': [{'items': []}]}]},
+                   export([], {'name': 'No id'}),
+                   {'accounts': 'none'}):
+            with self.assertRaises(onepassword.OnePasswordError):
+                onepassword.OnePuxSource(archive(js))
+
+
+if __name__ == '__main__':
+    unittest.main()
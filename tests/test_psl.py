@@ -0,0 +1,108 @@
+import contextlib
+import io
+import json
+import os
+import sys
+import tempfile
+import unittest
+from unittest import mock
+
+import convert
+import psl
+import sources
+from sources import Uri
+
+URIS = [
+    Uri('https://mail.example.com/inbox'),
+    Uri('https://app.example.org/', sources.MATCH_DOMAIN),
+    Uri('https://portal.acme.co.uk/', sources.MATCH_HOST),
+    Uri('https://a.b.net/path', sources.MATCH_STARTS_WITH),
+    Uri('https://login.exact.io/login', sources.MATCH_EXACT),
+    Uri(r'^https://(www\.)?regex\.com/.*', sources.MATCH_REGEX),
+    Uri(r'^https://(foo|bar)\.com', sources.MATCH_REGEX),
+    Uri('https://never.example.com', sources.MATCH_NEVER),
+]
+
+
+class ServiceNameTest(unittest.TestCase):
+    def test_service_name(self):
+        uri = 'https://www.example.co.uk:8443/login'
+        self.assertEqual(psl.service_name(uri), 'example.co.uk')
+        self.assertEqual(psl.service_name(uri, psl.HOST), 'www.example.co.uk')
+        self.assertEqual(psl.service_name(uri, psl.URI), uri)
+        self.assertEqual(psl.service_name('http://10.0.0.1:8080/'),
+                         '10.0.0.1:8080')
+        self.assertEqual(psl.service_name('nas:5000'), 'nas:5000')
+        self.assertEqual(psl.service_name('androidapp://com.example'),
+                         'androidapp://com.example')
+
+    def test_regex_domain(self):
+        self.assertEqual(psl.regex_domain(r'.*\.example\.co\.uk'),
+                         'example.co.uk')
+        for pattern in (r'^https://(foo|bar)\.com', r'example\.(com|org)',
+                        '.*'):
+            with self.assertRaisesRegex(psl.ServiceError, 'No single domain'):
+                psl.regex_domain(pattern)
+
+
+class MatchServiceTest(unittest.TestCase):
+    def services(self, mode):
+        result = []
+        for uri in URIS:
+            try:
+                result.append(psl.match_service(uri, mode))
+            except psl.ServiceError:
+                result.append('error')
+        return result
+
+    def test_domain(self):
+        self.assertEqual(self.services(psl.DOMAIN), [
+            'example.com', 'example.org', 'portal.acme.co.uk', 'a.b.net',
+            'login.exact.io', 'regex.com', 'error', None])
+
+    def test_host(self):
+        self.assertEqual(self.services(psl.HOST), [
+            'mail.example.com', 'example.org', 'portal.acme.co.uk',
+            'a.b.net', 'login.exact.io', 'regex.com', 'error', None])
+
+    def test_uri(self):
+        # Only URIs without a match detection are kept as is
+        self.assertEqual(self.services(psl.URI), [
+            'https://mail.example.com/inbox', 'example.org',
+            'portal.acme.co.uk', 'a.b.net', 'login.exact.io', 'regex.com',
+            'error', None])
+
+
+class ConvertTest(unittest.TestCase):
+    def test_report(self):
+        js = {'encrypted': False, 'folders': [], 'items': [{
+            'id': 'i1', 'type': 1, 'name': 'Site',
+            'login': {'username': 'alice', 'password': 'pw',
+                      'uris': [{'uri': u.uri, 'match': u.match}
+                               for u in URIS]}}]}
+
+        with tempfile.TemporaryDirectory() as d:
+            vault = os.path.join(d, 'vault.json')
+            output = os.path.join(d, 'out.csv')
+            with open(vault, 'w') as f:
+                json.dump(js, f)
+
+            out = io.StringIO()
+            argv = ['convert.py', '-f', vault, '-o', output]
+            with mock.patch.object(sys, 'argv', argv):
+                with contextlib.redirect_stdout(out):
+                    convert.main()
+            with open(output) as f:
+                services = [line.split(',')[0] for line in f]
+
+        self.assertEqual(services, [
+            'example.com', 'example.org', 'portal.acme.co.uk', 'a.b.net',
+            'login.exact.io', 'regex.com'])
+        self.assertIn(r"URI needs manual handling: Site: No single domain in "
+                      r"regex ^https://(foo|bar)\.com", out.getvalue())
+        self.assertIn("1 URIs with match detection 'never' skipped",
+                      out.getvalue())
+
+
+if __name__ == '__main__':
+    unittest.main()
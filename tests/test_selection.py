@@ -0,0 +1,153 @@
+import argparse
+import unittest
+
+import selection
+import sources
+
+VAULT = {
+    'folders': [
+        {'id': 'f1', 'name': 'Work'},
+        {'id': 'f2', 'name': 'Work/Clients'},
+        {'id': 'f3', 'name': 'Private'},
+    ],
+    'collections': [
+        {'id': 'c1', 'name': 'Shared'},
+    ],
+    'items': [
+        {'id': 'mail', 'folderId': 'f1', 'type': 1, 'name': 'Work Mail',
+         'favorite': True, 'revisionDate': '2024-03-01T10:00:00.000Z',
+         'login': {'username': 'alice',
+                   'uris': [{'uri': 'https://mail.example.com/'}]}},
+        {'id': 'acme', 'folderId': 'f2', 'type': 1, 'name': 'Acme',
+         'revisionDate': '2023-05-01T10:00:00.000Z',
+         'organizationId': 'o1', 'collectionIds': ['c1'],
+         'login': {'username': 'Alice',
+                   'uris': [{'uri': 'portal.acme.co.uk'}]}},
+        {'id': 'bank', 'folderId': 'f3', 'type': 1, 'name': 'Bank',
+         'login': {'username': 'bob',
+                   'uris': [{'uri': 'https://notexample.com'}]}},
+        {'id': 'loose', 'folderId': None, 'type': 2, 'name': 'Loose note',
+         'revisionDate': '2025-01-01T10:00:00.000Z'},
+        {'id': 'old', 'folderId': 'f1', 'type': 1, 'name': 'Old Mail',
+         'deletedDate': '2024-01-01T00:00:00.000Z',
+         'login': {'username': 'alice', 'uris': []}},
+    ],
+}
+
+
+def rules(*values):
+    return [selection.parse_rule(v) for v in values]
+
+
+class SelectionTest(unittest.TestCase):
+    def setUp(self):
+        self.source = sources.BitwardenSource(VAULT)
+
+    def selected(self, include=(), exclude=(), **kwargs):
+        s = selection.Selection(self.source, rules(*include),
+                                rules(*exclude), **kwargs)
+        return [e.id for e in self.source.entries() if s.selected(e)]
+
+    def test_default(self):
+        # Trashed items are left out unless asked for
+        self.assertEqual(self.selected(), ['mail', 'acme', 'bank', 'loose'])
+        self.assertEqual(self.selected(trashed=selection.ONLY), ['old'])
+        self.assertEqual(self.selected(trashed=selection.INCLUDE),
+                         ['mail', 'acme', 'bank', 'loose', 'old'])
+
+    def test_folders(self):
+        self.assertEqual(self.selected(['Work']), ['mail'])
+        self.assertEqual(self.selected(['folder:Work'], recursive=True),
+                         ['mail', 'acme'])
+        self.assertEqual(self.selected([selection.NO_FOLDER]), ['loose'])
+
+    def test_same_key_or(self):
+        self.assertEqual(self.selected(['Work', 'Private']),
+                         ['mail', 'bank'])
+        self.assertEqual(self.selected(['username:ALICE', 'username:bob']),
+                         ['mail', 'acme', 'bank'])
+        self.assertEqual(self.selected(['type:login', 'type:note']),
+                         ['mail', 'acme', 'bank', 'loose'])
+
+    def test_cross_key_and(self):
+        self.assertEqual(self.selected(['Work', 'favorite:yes'],
+                                       recursive=True), ['mail'])
+        self.assertEqual(self.selected(['type:login', 'username:alice']),
+                         ['mail', 'acme'])
+        self.assertEqual(self.selected(['name:mail', 'Private']), [])
+
+    def test_exclude_wins(self):
+        self.assertEqual(self.selected(['type:login'], ['name:^acme$']),
+                         ['mail', 'bank'])
+        self.assertEqual(self.selected(['Work'], ['Work']), [])
+        self.assertEqual(self.selected([], ['org:any']),
+                         ['mail', 'bank', 'loose'])
+
+    def test_unfoldered(self):
+        # include keeps items without a folder past folder rules
+        self.assertEqual(self.selected(['Work'],
+                                       unfoldered=selection.INCLUDE),
+                         ['mail', 'loose'])
+        self.assertEqual(self.selected(['Work', 'type:note'],
+                                       unfoldered=selection.INCLUDE),
+                         ['loose'])
+        self.assertEqual(self.selected([], ['Work'],
+                                       unfoldered=selection.INCLUDE),
+                         ['acme', 'bank', 'loose'])
+        self.assertEqual(self.selected(unfoldered=selection.EXCLUDE),
+                         ['mail', 'acme', 'bank'])
+
+    def test_revised(self):
+        self.assertEqual(self.selected(['revised:2024-01-01..']),
+                         ['mail', 'loose'])
+        self.assertEqual(self.selected(['revised:..2023-12-31']), ['acme'])
+        self.assertEqual(self.selected(['revised:2024-03-01..2024-03-01']),
+                         ['mail'])
+        # Items without a date never match
+        self.assertEqual(self.selected(['revised:..']),
+                         ['mail', 'acme', 'loose'])
+
+    def test_domain(self):
+        # Suffix match on label boundaries, bare hosts included
+        self.assertEqual(self.selected(['domain:example.com']), ['mail'])
+        self.assertEqual(self.selected(['domain:Mail.Example.com']),
+                         ['mail'])
+        self.assertEqual(self.selected(['domain:co.uk']), ['acme'])
+
+    def test_organization(self):
+        self.assertEqual(self.selected(['org:o1']), ['acme'])
+        self.assertEqual(self.selected(['collection:Shared']), ['acme'])
+        self.assertEqual(
+            self.selected(organization=selection.EXCLUDE),
+            ['mail', 'bank', 'loose'])
+        self.assertEqual(self.selected(organization=selection.ONLY),
+                         ['acme'])
+
+    def test_unknown_groups(self):
+        for include in (['Nowhere'], ['collection:Nowhere']):
+            with self.assertRaisesRegex(selection.SelectionError, 'Nowhere'):
+                self.selected(include)
+        with self.assertRaises(selection.SelectionError):
+            self.selected([], ['folder:Clients'])
+
+
+class ParseRuleTest(unittest.TestCase):
+    def test_parse(self):
+        self.assertEqual(selection.parse_rule('Work'),
+                         selection.Rule('folder', 'Work'))
+        self.assertEqual(selection.parse_rule('type:note'),
+                         selection.Rule('type', 'note'))
+        # Unknown keys are folder names with a colon
+        self.assertEqual(selection.parse_rule('Team: Ops'),
+                         selection.Rule('folder', 'Team: Ops'))
+
+    def test_invalid(self):
+        for value in ('name:(', 'type:wallet', 'favorite:maybe',
+                      'revised:2024', 'revised:2024-1-1..',
+                      'revised:..yesterday'):
+            with self.assertRaises(argparse.ArgumentTypeError):
+                selection.parse_rule(value)
+
+
+if __name__ == '__main__':
+    unittest.main()
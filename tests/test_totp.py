@@ -0,0 +1,80 @@
+import unittest
+
+import mooltipass
+import totp
+from sources import LOGIN, Entry, Uri
+
+SECRET = b'Hello!\xde\xad\xbe\xef'
+BASE32 = 'JBSWY3DPEHPK3PXP'
+
+
+class ParseTest(unittest.TestCase):
+    def test_base32(self):
+        t = totp.parse(' jbsw y3dp-ehpk 3pxp ')
+        self.assertEqual(t, totp.Totp(SECRET))
+        self.assertEqual(t.base32(), BASE32)
+
+    def test_otpauth(self):
+        t = totp.parse(f"otpauth://totp/Example:alice?secret={BASE32}"
+                       "&issuer=Example&digits=8&period=60&algorithm=sha256")
+        self.assertEqual(t, totp.Totp(SECRET, digits=8, period=60,
+                                      algorithm='SHA256'))
+        with self.assertRaisesRegex(totp.TotpError, 'SHA256'):
+            totp.check(t)
+
+        t = totp.parse(f"otpauth://totp/alice?secret={BASE32}&period=255")
+        totp.check(t)
+        self.assertEqual((t.digits, t.period, t.algorithm), (6, 255, 'SHA1'))
+
+    def test_hotp(self):
+        with self.assertRaisesRegex(totp.TotpError, 'HOTP'):
+            totp.parse(f"otpauth://hotp/alice?secret={BASE32}&counter=1")
+
+    def test_steam(self):
+        t = totp.parse(f"steam://{BASE32}")
+        self.assertEqual(t, totp.Totp(SECRET, digits=5, steam=True))
+        t = totp.parse(f"otpauth://totp/Steam?secret={BASE32}&encoder=steam")
+        self.assertTrue(t.steam)
+        with self.assertRaisesRegex(totp.TotpError, 'Steam'):
+            totp.check(t)
+
+    def test_invalid(self):
+        for value in ('not base32!', '', 'otpauth://totp/alice?secret=1',
+                      f"otpauth://totp/alice?secret={BASE32}&digits=x",
+                      'https://example.com'):
+            with self.assertRaises(totp.TotpError):
+                totp.parse(value)
+
+        with self.assertRaisesRegex(totp.TotpError, 'digit'):
+            totp.check(totp.Totp(SECRET, digits=10))
+        with self.assertRaisesRegex(totp.TotpError, 'period'):
+            totp.check(totp.Totp(SECRET, period=256))
+        with self.assertRaisesRegex(totp.TotpError, 'longer'):
+            totp.check(totp.Totp(b'x' * 65))
+
+
+class CredentialsTest(unittest.TestCase):
+    def test_credentials(self):
+        site = Entry('Site', LOGIN, username='alice', totp=BASE32,
+                     uris=[Uri('https://a.example.com', service='example.com'),
+                           Uri('https://b.example.com', service='example.com'),
+                           Uri('https://other.org', service='other.org')])
+        bare = Entry('Bare', LOGIN, username='bob', totp=BASE32)
+        steam = Entry('Steam', LOGIN, totp=f"steam://{BASE32}",
+                      uris=[Uri('https://steampowered.com')])
+        plain = Entry('Plain', LOGIN, uris=[Uri('https://plain.com')])
+
+        records, failed = mooltipass.totp_credentials([site, bare, steam,
+                                                       plain])
+        # One record per service, not per URI
+        self.assertEqual([(r['service'], r['login']) for r in records],
+                         [('example.com', 'alice'), ('other.org', 'alice')])
+        self.assertEqual(records[0]['totp']['totp_secret_key'], BASE32)
+        self.assertEqual([(e.name, str(reason)) for e, reason in failed], [
+            ('Bare', "No URI to attach the secret to"),
+            ('Steam', "Steam Guard codes aren't supported by the device"),
+        ])
+
+
+if __name__ == '__main__':
+    unittest.main()
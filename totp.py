@@ -0,0 +1,83 @@
+import base64
+import binascii
+import urllib.parse
+from dataclasses import dataclass
+
+# What the Mooltipass BLE authenticator can generate
+ALGORITHMS = ('SHA1',)
+DIGITS = (6, 7, 8)
+MAX_PERIOD = 255
+MAX_SECRET_LEN = 64
+
+
+class TotpError(Exception):
+    pass
+
+
+@dataclass
+class Totp:
+    secret: bytes
+    digits: int = 6
+    period: int = 30
+    algorithm: str = 'SHA1'
+    steam: bool = False
+
+    def base32(self):
+        return base64.b32encode(self.secret).decode().rstrip('=')
+
+
+def b32decode(secret):
+    secret = secret.replace(' ', '').replace('-', '').upper().rstrip('=')
+    if not secret:
+        raise TotpError("Empty secret")
+    try:
+        return base64.b32decode(secret + '=' * (-len(secret) % 8))
+    except binascii.Error:
+        raise TotpError("Secret is not valid base32")
+
+
+def parse_uri(value):
+    url = urllib.parse.urlparse(value)
+    if url.netloc.lower() != 'totp':
+        raise TotpError(f"{url.netloc.upper()} isn't supported, only TOTP")
+
+    query = {k.lower(): v[0] for k, v in
+             urllib.parse.parse_qs(url.query).items()}
+    try:
+        totp = Totp(b32decode(query.get('secret', '')),
+                    digits=int(query.get('digits', 6)),
+                    period=int(query.get('period', 30)),
+                    algorithm=query.get('algorithm', 'SHA1').upper())
+    except ValueError:
+        raise TotpError("Invalid digits or period")
+
+    # Some authenticators tag Steam secrets this way instead of steam://
+    totp.steam = query.get('encoder', '').lower() == 'steam'
+    return totp
+
+
+def parse(value):
+    value = value.strip()
+    scheme = value.split('://', 1)[0].lower() if '://' in value else None
+    if scheme == 'otpauth':
+        return parse_uri(value)
+    if scheme == 'steam':
+        return Totp(b32decode(value.split('://', 1)[1]), digits=5, steam=True)
+    if scheme is not None:
+        raise TotpError(f"Unknown scheme {scheme}://")
+    return Totp(b32decode(value))
+
+
+def check(totp):
+    if totp.steam:
+        raise TotpError("Steam Guard codes aren't supported by the device")
+    if totp.algorithm not in ALGORITHMS:
+        raise TotpError(f"{totp.algorithm} isn't supported by the device")
+    if totp.digits not in DIGITS:
+        raise TotpError(f"{totp.digits} digit codes aren't supported by the "
+                        "device")
+    if not 0 < totp.period <= MAX_PERIOD:
+        raise TotpError(f"A {totp.period}s period isn't supported by the "
+                        "device")
+    if len(totp.secret) > MAX_SECRET_LEN:
+        raise TotpError(f"Secret is longer than {MAX_SECRET_LEN} bytes")